@@ -1,39 +1,26 @@
-use tonic::Request;
-// use tonic_health::proto::health_client::HealthClient;
-// use tonic_health::proto::HealthCheckRequest;
-// use sinergasm::protos::health::health_server::HealthServer;
-
-
-
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-  let options = Arc::new(Options::new("desktop".into()));
-  let client = create_client(&options).await?;
-
-  let options = Options::new("cli".into());
-    let channel = tonic::transport::Channel::from_static("http://[::1]:50051")
-        .connect()
-        .await?;
-
-    let mut client = HealthClient::new(channel);
-
-    let request = Request::new(HealthCheckRequest {
-        service: "".into(), // Specify the service name if needed
-    });
-
-    let response = client.check(request).await?;
-
-    println!("Health check response: {:?}", response);
-
-
-
-//   {
-//     let request = msg::ListRequest {};
-//     let response = client.list_workspaces(request).await;
-//     if let Ok(response) = response {
-//       println!("Response: {:?}", response);
-//     }
-//   }
-
-    Ok(())
-}
\ No newline at end of file
+use sinnergasm::grpc_client::create_channel;
+use sinnergasm::grpc_client::create_interceptor;
+use sinnergasm::options::Options;
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+use tonic_health::pb::HealthCheckRequest;
+
+// The empty name is the server as a whole
+const SERVICES: [&str; 2] = ["", "sinnergasm.VirtualWorkspaces"];
+
+// Prints the status the server reports for itself and for the workspaces, true when both are serving
+pub(crate) async fn check_health(options: &Options) -> anyhow::Result<bool> {
+  let mut client = HealthClient::with_interceptor(create_channel(options).await?, create_interceptor(options)?);
+  let mut serving = true;
+  for service in SERVICES {
+    let response = client
+      .check(HealthCheckRequest { service: service.into() })
+      .await?
+      .into_inner();
+    let status = ServingStatus::from_i32(response.status).unwrap_or(ServingStatus::Unknown);
+    let name = if service.is_empty() { "server" } else { service };
+    println!("{:<30} {}", name, status.as_str_name());
+    serving &= status == ServingStatus::Serving;
+  }
+  Ok(serving)
+}
@@ -1,20 +1,163 @@
 
-use tonic::Request;
 use std::sync::Arc;
+use clap::Parser;
+use clap::Subcommand;
+use sinnergasm::options::ClientArgs;
 use sinnergasm::options::Options;
 use sinnergasm::grpc_client::create_client;
+use sinnergasm::latency;
+use sinnergasm::logging::init_logging;
+use sinnergasm::telemetry;
 use sinnergasm::protos as msg;
+use tracing::Instrument;
 
+mod health;
+
+#[derive(Parser, Debug)]
+#[command(name = "sinctl", about = "Manage a sinnergasm workspace")]
+struct CliArgs {
+  #[command(flatten)]
+  client: ClientArgs,
+  // Closes the workspace when no command is given
+  #[command(subcommand)]
+  command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  #[command(flatten)]
+  Workspace(WorkspaceCommand),
+  /// Check that the server is up and its actors are running, exits with 1 when it is not serving
+  Health,
+}
+
+// The commands that need a client of the workspaces service
+#[derive(Subcommand, Debug)]
+enum WorkspaceCommand {
+  /// Close the workspace and disconnect every device
+  Close,
+  /// Move the target of the workspace to a device
+  Target { device: String },
+  /// Show the target, the controller and the connected devices of the workspace
+  Status,
+  /// Show how long input took to reach each simulator of the workspace
+  Latency,
+}
+
+fn print_latency(name: &str, report: &msg::LatencyReport) {
+  let hops = [
+    ("capture to relay", &report.capture_to_relay),
+    ("relay to inject", &report.relay_to_inject),
+    ("capture to inject", &report.capture_to_inject),
+  ];
+  println!("{} ({} missed)", name, report.missed);
+  for (hop, histogram) in hops {
+    let Some(histogram) = histogram else {
+      continue;
+    };
+    let millis = |micros: Option<u64>| micros.map_or("-".into(), |micros| format!("{:.1}ms", micros as f64 / 1000.0));
+    println!(
+      "  {:<18} {:>8} samples  mean {:>8}  p50 {:>8}  p99 {:>8}  max {:>8}",
+      hop,
+      latency::samples(histogram),
+      millis(latency::mean_us(histogram)),
+      millis(latency::quantile_us(histogram, 0.5)),
+      millis(latency::quantile_us(histogram, 0.99)),
+      millis(Some(histogram.max_us)),
+    );
+  }
+}
+
+fn or_none(value: &str) -> &str {
+  if value.is_empty() {
+    "none"
+  } else {
+    value
+  }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-  let options = Arc::new(Options::new("desktop".into()));
+  let args = CliArgs::parse();
+  let options = Arc::new(Options::load(&args.client, "desktop")?);
+  init_logging("sinctl", options.trace_export.as_ref())?;
+  let command = match args.command {
+    Some(Command::Health) => {
+      let serving = health::check_health(&options).await?;
+      telemetry::shutdown();
+      std::process::exit(if serving { 0 } else { 1 });
+    }
+    Some(Command::Workspace(command)) => command,
+    None => WorkspaceCommand::Close,
+  };
   let mut client = create_client(&options).await?;
 
-  println!("Sending close workspace request");
-  client.close_workspace(msg::CloseRequest {
-    workspace: options.workspace.clone(),
-  }).await?;
+  match command {
+    WorkspaceCommand::Close => {
+      tracing::info!("Sending close workspace request");
+      client.close_workspace(msg::CloseRequest {
+        workspace: options.workspace.clone(),
+      }).instrument(tracing::info_span!("close")).await?;
+    }
+    WorkspaceCommand::Target { device } => {
+      let span = tracing::info_span!("target", device = %device);
+      let response = client
+        .target_device(msg::TargetRequest {
+          workspace: options.workspace.clone(),
+          device,
+          clipboard: None,
+          take_control: false,
+        })
+        .instrument(span)
+        .await?
+        .into_inner();
+      if response.changed {
+        println!("Moved the target from {} to {}", or_none(&response.previous), response.current);
+      } else {
+        println!("{} is already the target", response.current);
+      }
+    }
+    WorkspaceCommand::Status => {
+      let status = client
+        .get_workspace_status(msg::StatusRequest {
+          workspace: options.workspace.clone(),
+        })
+        .await?
+        .into_inner();
+      let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis() as u64;
+      let workspace = status.workspace.unwrap_or_default();
+      println!("Workspace:  {}", workspace.name);
+      println!("Target:     {}", or_none(&workspace.target));
+      println!("Controller: {}", or_none(&workspace.controller));
+      for session in status.sessions {
+        let role = msg::DeviceRole::from_i32(session.role).unwrap_or(msg::DeviceRole::Unknown);
+        println!(
+          "  {:<20} {:<12} connected {}s ago",
+          session.device,
+          role.as_str_name().to_lowercase(),
+          now.saturating_sub(session.connected_at) / 1000
+        );
+      }
+    }
+    WorkspaceCommand::Latency => {
+      let stats = client
+        .get_latency_stats(msg::LatencyStatsRequest {
+          workspace: options.workspace.clone(),
+        })
+        .await?
+        .into_inner();
+      for device in stats.devices {
+        if let Some(report) = device.report {
+          print_latency(&report.device, &report);
+        }
+      }
+      if let Some(total) = stats.total {
+        print_latency("Total", &total);
+      }
+    }
+  }
 
 //   {
 //     let request = msg::ListRequest {};
@@ -24,5 +167,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //     }
 //   }
 
+    telemetry::shutdown();
     Ok(())
-}
\ No newline at end of file
+}
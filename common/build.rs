@@ -8,6 +8,12 @@
 // }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-  tonic_build::compile_protos("src/sinnergasm.proto")?;
+  let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
+  tonic_build::configure()
+    // Served by the reflection service, so the api can be explored without the proto file
+    .file_descriptor_set_path(out_dir.join("sinnergasm_descriptor.bin"))
+    .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+    .message_attribute(".", "#[serde(default)]")
+    .compile(&["src/sinnergasm.proto"], &["src/"])?;
   Ok(())
 }
@@ -10,6 +10,7 @@ use tonic::service::Interceptor;
 
 use crate::options::Options;
 use crate::protos::virtual_workspaces_client::VirtualWorkspacesClient;
+use crate::telemetry;
 use anyhow;
 use tonic::metadata::MetadataValue;
 
@@ -29,6 +30,8 @@ impl AuthorizationInterceptor {
 impl Interceptor for AuthorizationInterceptor {
   fn call(&mut self, mut request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
     request.metadata_mut().insert("authorization", self.token.clone());
+    // Lets the server continue the trace of the span the request is sent from
+    telemetry::inject(request.metadata_mut());
     Ok::<_, Status>(request)
   }
 }
@@ -37,7 +40,8 @@ impl Interceptor for AuthorizationInterceptor {
 //   inner: VirtualWorkspacesClient<tonic::codegen::InterceptedService<Channel, AuthorizationInterceptor>>,
 // }
 
-pub async fn create_client(options: &Options) -> Result<GrpcClient, anyhow::Error> {
+// The channel is shared by every service the server offers, not only the workspaces
+pub async fn create_channel(options: &Options) -> Result<Channel, anyhow::Error> {
   let cert = std::fs::read("keys/ca.crt")?;
   let channel = Channel::from_shared(options.base_url.clone())?
     .tls_config(
@@ -48,7 +52,17 @@ pub async fn create_client(options: &Options) -> Result<GrpcClient, anyhow::Erro
     .concurrency_limit(options.concurrency_limit);
   let connect_future = channel.connect();
   let channel = timeout(Duration::from_secs(options.timeout), connect_future).await??;
-  let interceptor = AuthorizationInterceptor::new(format!("Bearer {}", options.token).parse()?);
-  let client = VirtualWorkspacesClient::with_interceptor(channel, interceptor);
+  Ok(channel)
+}
+
+pub fn create_interceptor(options: &Options) -> Result<AuthorizationInterceptor, anyhow::Error> {
+  Ok(AuthorizationInterceptor::new(
+    format!("Bearer {}", options.token).parse()?,
+  ))
+}
+
+pub async fn create_client(options: &Options) -> Result<GrpcClient, anyhow::Error> {
+  let channel = create_channel(options).await?;
+  let client = VirtualWorkspacesClient::with_interceptor(channel, create_interceptor(options)?);
   Ok(client)
 }
@@ -0,0 +1,178 @@
+use crate::protos as msg;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+// The upper bounds of the histogram buckets, from half a millisecond to two seconds
+const BOUNDS_US: [u64; 12] = [
+  500, 1_000, 2_000, 5_000, 10_000, 20_000, 50_000, 100_000, 200_000, 500_000, 1_000_000, 2_000_000,
+];
+
+// Microseconds since the unix epoch on the local clock
+pub fn now_us() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|elapsed| elapsed.as_micros() as u64)
+    .unwrap_or_default()
+}
+
+pub fn histogram() -> msg::LatencyHistogram {
+  msg::LatencyHistogram {
+    bounds_us: BOUNDS_US.to_vec(),
+    counts: vec![0; BOUNDS_US.len() + 1],
+    sum_us: 0,
+    max_us: 0,
+  }
+}
+
+pub fn report(workspace: &str, device: &str) -> msg::LatencyReport {
+  msg::LatencyReport {
+    workspace: workspace.into(),
+    device: device.into(),
+    capture_to_relay: Some(histogram()),
+    relay_to_inject: Some(histogram()),
+    capture_to_inject: Some(histogram()),
+    missed: 0,
+  }
+}
+
+pub fn record(histogram: &mut msg::LatencyHistogram, latency_us: u64) {
+  let bucket = histogram
+    .bounds_us
+    .iter()
+    .position(|bound| latency_us <= *bound)
+    .unwrap_or(histogram.bounds_us.len());
+  if let Some(count) = histogram.counts.get_mut(bucket) {
+    *count += 1;
+    histogram.sum_us += latency_us;
+    histogram.max_us = histogram.max_us.max(latency_us);
+  }
+}
+
+// Histograms with other buckets cannot be added up, so they are left out
+pub fn merge(into: &mut msg::LatencyHistogram, other: &msg::LatencyHistogram) {
+  if into.bounds_us != other.bounds_us || into.counts.len() != other.counts.len() {
+    return;
+  }
+  for (count, other) in into.counts.iter_mut().zip(other.counts.iter()) {
+    *count += other;
+  }
+  into.sum_us += other.sum_us;
+  into.max_us = into.max_us.max(other.max_us);
+}
+
+pub fn merge_report(into: &mut msg::LatencyReport, other: &msg::LatencyReport) {
+  let hops = [
+    (&mut into.capture_to_relay, &other.capture_to_relay),
+    (&mut into.relay_to_inject, &other.relay_to_inject),
+    (&mut into.capture_to_inject, &other.capture_to_inject),
+  ];
+  for (into, other) in hops {
+    if let (Some(into), Some(other)) = (into.as_mut(), other.as_ref()) {
+      merge(into, other);
+    }
+  }
+  into.missed += other.missed;
+}
+
+pub fn samples(histogram: &msg::LatencyHistogram) -> u64 {
+  histogram.counts.iter().sum()
+}
+
+pub fn mean_us(histogram: &msg::LatencyHistogram) -> Option<u64> {
+  match samples(histogram) {
+    0 => None,
+    samples => Some(histogram.sum_us / samples),
+  }
+}
+
+// The upper bound of the bucket the quantile falls in, or the maximum when that is the last bucket
+pub fn quantile_us(histogram: &msg::LatencyHistogram, quantile: f64) -> Option<u64> {
+  let samples = samples(histogram);
+  if samples == 0 {
+    return None;
+  }
+  let rank = ((samples as f64 * quantile).ceil() as u64).clamp(1, samples);
+  let mut seen = 0;
+  for (bucket, count) in histogram.counts.iter().enumerate() {
+    seen += count;
+    if seen >= rank {
+      let bound = histogram.bounds_us.get(bucket).copied().unwrap_or(histogram.max_us);
+      return Some(bound.min(histogram.max_us));
+    }
+  }
+  Some(histogram.max_us)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn recorded(latencies_us: &[u64]) -> msg::LatencyHistogram {
+    let mut histogram = histogram();
+    for latency_us in latencies_us {
+      record(&mut histogram, *latency_us);
+    }
+    histogram
+  }
+
+  #[test]
+  fn records_into_the_first_bucket_that_holds_the_latency() {
+    let histogram = recorded(&[0, 500, 501, 2_000_000, 2_000_001]);
+
+    let mut counts = vec![0; BOUNDS_US.len() + 1];
+    counts[0] = 2;
+    counts[1] = 1;
+    counts[BOUNDS_US.len() - 1] = 1;
+    // Past the last bound
+    counts[BOUNDS_US.len()] = 1;
+    assert_eq!(histogram.counts, counts);
+    assert_eq!(histogram.sum_us, 4_001_002);
+    assert_eq!(histogram.max_us, 2_000_001);
+  }
+
+  #[test]
+  fn merges_histograms_with_the_same_buckets() {
+    let mut into = recorded(&[100, 3_000]);
+    merge(&mut into, &recorded(&[100, 3_000_000]));
+
+    assert_eq!(samples(&into), 4);
+    assert_eq!(into.counts[0], 2);
+    assert_eq!(into.counts[3], 1);
+    assert_eq!(into.counts[BOUNDS_US.len()], 1);
+    assert_eq!(into.sum_us, 3_003_200);
+    assert_eq!(into.max_us, 3_000_000);
+  }
+
+  #[test]
+  fn leaves_out_histograms_with_other_buckets() {
+    let mut into = recorded(&[100]);
+    let other = msg::LatencyHistogram {
+      bounds_us: vec![1_000],
+      counts: vec![5, 5],
+      sum_us: 10_000,
+      max_us: 2_000,
+    };
+    merge(&mut into, &other);
+
+    assert_eq!(into, recorded(&[100]));
+  }
+
+  #[test]
+  fn quantiles_are_the_bound_of_their_bucket() {
+    let histogram = recorded(&[100, 200, 300, 4_000]);
+
+    assert_eq!(quantile_us(&histogram, 0.0), Some(500));
+    assert_eq!(quantile_us(&histogram, 0.5), Some(500));
+    assert_eq!(quantile_us(&histogram, 0.75), Some(500));
+    // The last sample is below the bound of its bucket
+    assert_eq!(quantile_us(&histogram, 0.76), Some(4_000));
+    assert_eq!(quantile_us(&histogram, 1.0), Some(4_000));
+  }
+
+  #[test]
+  fn quantiles_past_the_last_bound_are_the_maximum() {
+    assert_eq!(quantile_us(&recorded(&[5_000_000]), 0.99), Some(5_000_000));
+    assert_eq!(quantile_us(&histogram(), 0.5), None);
+    assert_eq!(mean_us(&histogram()), None);
+  }
+}
@@ -3,10 +3,15 @@
 // }
 
 pub mod grpc_client;
+pub mod latency;
+pub mod logging;
 pub mod options;
+pub mod telemetry;
 
 pub mod protos {
   tonic::include_proto!("sinnergasm"); // The string specified here must match the proto package name
+
+  pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("sinnergasm_descriptor");
 }
 
 pub enum UserInputEvent {
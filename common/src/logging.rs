@@ -0,0 +1,22 @@
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::telemetry;
+use crate::telemetry::TraceExport;
+
+// The clients log to stderr, filtered by RUST_LOG when it is set.
+// The spans are also exported when a trace export is configured.
+pub fn init_logging(service: &str, trace_export: Option<&TraceExport>) -> anyhow::Result<()> {
+  let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+  let telemetry = match trace_export {
+    Some(export) => Some(telemetry::layer(service, export)?),
+    None => None,
+  };
+  tracing_subscriber::registry()
+    .with(filter)
+    .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+    .with(telemetry)
+    .init();
+  Ok(())
+}
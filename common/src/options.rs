@@ -4,18 +4,188 @@
 // pub const HOST: &str =
 //   "sinnergy-nlb-bdef3e305c57149f.elb.us-west-1.amazonaws.com";
 
+use std::path::Path;
+use std::path::PathBuf;
 use std::time::Duration;
 
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::telemetry::TraceExport;
+
 pub const HOST: &str = "sinnergy-nlb-107b6dacc13a52b2.elb.us-west-1.amazonaws.com";
 // pub const HOST: &str = "10.0.0.129";
 
 pub const PORT: i64 = 50051;
 
-pub fn read_token() -> String {
-  std::fs::read_to_string("./keys/token.txt")
-    .expect("Unable to read token.")
+// Read from the working directory when neither --config nor SINNERGASM_CLIENT_CONFIG is given
+pub const DEFAULT_CONFIG_PATH: &str = "./sinnergasm.toml";
+pub const DEFAULT_TOKEN_PATH: &str = "./keys/token.txt";
+
+pub fn read_token(path: &Path) -> anyhow::Result<String> {
+  let token: String = std::fs::read_to_string(path)
+    .with_context(|| format!("Unable to read token from {:?}", path))?
     .trim()
-    .into()
+    .into();
+  anyhow::ensure!(!token.is_empty(), "The token in {:?} is empty", path);
+  Ok(token)
+}
+
+// Command line flags, these take precedence over the environment and the config file
+#[derive(clap::Parser, Debug, Clone, Default)]
+pub struct ClientArgs {
+  #[arg(long)]
+  pub config: Option<PathBuf>,
+  #[arg(long)]
+  pub base_url: Option<String>,
+  #[arg(long)]
+  pub token: Option<String>,
+  #[arg(long)]
+  pub token_file: Option<PathBuf>,
+  #[arg(long)]
+  pub workspace: Option<String>,
+  #[arg(long)]
+  pub device: Option<String>,
+  // Seconds to wait for the connection to the server
+  #[arg(long)]
+  pub timeout: Option<u64>,
+  // Milliseconds between mouse flushes on the controller
+  #[arg(long)]
+  pub controller_mouse_frequency: Option<u64>,
+  #[arg(long)]
+  pub shared_folder: Option<String>,
+  // Seconds between heartbeats sent to the server
+  #[arg(long)]
+  pub heartbeat_interval: Option<u64>,
+  // Seconds without any message from the server before the connection is considered dead
+  #[arg(long)]
+  pub heartbeat_timeout: Option<u64>,
+  // Capture and simulate on the same device, control follows whichever keyboard or mouse is used
+  #[arg(long)]
+  pub symmetric: bool,
+  // Sends the spans to an OTLP collector, such as http://localhost:4317
+  #[arg(long)]
+  pub otlp_endpoint: Option<String>,
+  // Appends the spans to a file as json instead
+  #[arg(long)]
+  pub trace_file: Option<PathBuf>,
+}
+
+// Every layer uses the same shape, unset fields fall through to the layer below
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ClientLayer {
+  base_url: Option<String>,
+  token: Option<String>,
+  token_file: Option<PathBuf>,
+  workspace: Option<String>,
+  device: Option<String>,
+  timeout: Option<u64>,
+  controller_mouse_frequency: Option<u64>,
+  shared_folder: Option<String>,
+  heartbeat_interval: Option<u64>,
+  heartbeat_timeout: Option<u64>,
+  symmetric: Option<bool>,
+  otlp_endpoint: Option<String>,
+  trace_file: Option<PathBuf>,
+}
+
+impl ClientLayer {
+  fn from_file(path: &Path) -> anyhow::Result<Self> {
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Unable to read config file {:?}", path))?;
+    toml::from_str(&contents).with_context(|| format!("Unable to parse config file {:?}", path))
+  }
+
+  fn from_env() -> anyhow::Result<Self> {
+    Ok(Self {
+      base_url: env_var("SINNERGASM_BASE_URL"),
+      token: env_var("SINNERGASM_TOKEN"),
+      token_file: env_var("SINNERGASM_TOKEN_FILE").map(PathBuf::from),
+      workspace: env_var("SINNERGASM_WORKSPACE"),
+      device: env_var("SINNERGASM_DEVICE"),
+      timeout: env_number("SINNERGASM_TIMEOUT")?,
+      controller_mouse_frequency: env_number("SINNERGASM_CONTROLLER_MOUSE_FREQUENCY")?,
+      shared_folder: env_var("SINNERGASM_SHARED_FOLDER"),
+      heartbeat_interval: env_number("SINNERGASM_HEARTBEAT_INTERVAL")?,
+      heartbeat_timeout: env_number("SINNERGASM_HEARTBEAT_TIMEOUT")?,
+      symmetric: env_flag("SINNERGASM_SYMMETRIC")?,
+      otlp_endpoint: env_var("SINNERGASM_OTLP_ENDPOINT"),
+      trace_file: env_var("SINNERGASM_TRACE_FILE").map(PathBuf::from),
+    })
+  }
+
+  fn from_args(args: &ClientArgs) -> Self {
+    Self {
+      base_url: args.base_url.clone(),
+      token: args.token.clone(),
+      token_file: args.token_file.clone(),
+      workspace: args.workspace.clone(),
+      device: args.device.clone(),
+      timeout: args.timeout,
+      controller_mouse_frequency: args.controller_mouse_frequency,
+      shared_folder: args.shared_folder.clone(),
+      heartbeat_interval: args.heartbeat_interval,
+      heartbeat_timeout: args.heartbeat_timeout,
+      // A flag that is not given leaves the lower layers in charge
+      symmetric: args.symmetric.then_some(true),
+      otlp_endpoint: args.otlp_endpoint.clone(),
+      trace_file: args.trace_file.clone(),
+    }
+  }
+
+  fn merge(self, over: Self) -> Self {
+    // A token given at a higher layer replaces a token file given below it and vice versa
+    let (token, token_file) = if over.token.is_some() || over.token_file.is_some() {
+      (over.token, over.token_file)
+    } else {
+      (self.token, self.token_file)
+    };
+    // The same goes for the two ways of exporting traces
+    let (otlp_endpoint, trace_file) = if over.otlp_endpoint.is_some() || over.trace_file.is_some() {
+      (over.otlp_endpoint, over.trace_file)
+    } else {
+      (self.otlp_endpoint, self.trace_file)
+    };
+    Self {
+      base_url: over.base_url.or(self.base_url),
+      token,
+      token_file,
+      workspace: over.workspace.or(self.workspace),
+      device: over.device.or(self.device),
+      timeout: over.timeout.or(self.timeout),
+      controller_mouse_frequency: over.controller_mouse_frequency.or(self.controller_mouse_frequency),
+      shared_folder: over.shared_folder.or(self.shared_folder),
+      heartbeat_interval: over.heartbeat_interval.or(self.heartbeat_interval),
+      heartbeat_timeout: over.heartbeat_timeout.or(self.heartbeat_timeout),
+      symmetric: over.symmetric.or(self.symmetric),
+      otlp_endpoint,
+      trace_file,
+    }
+  }
+}
+
+fn env_var(name: &str) -> Option<String> {
+  std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn env_number(name: &str) -> anyhow::Result<Option<u64>> {
+  env_var(name)
+    .map(|value| {
+      value
+        .parse()
+        .with_context(|| format!("{} must be a number, got {:?}", name, value))
+    })
+    .transpose()
+}
+
+fn env_flag(name: &str) -> anyhow::Result<Option<bool>> {
+  env_var(name)
+    .map(|value| match value.to_lowercase().as_str() {
+      "1" | "true" | "yes" => Ok(true),
+      "0" | "false" | "no" => Ok(false),
+      _ => anyhow::bail!("{} must be true or false, got {:?}", name, value),
+    })
+    .transpose()
 }
 
 #[derive(Clone)]
@@ -29,20 +199,157 @@ pub struct Options {
   pub controller_mouse_frequency: Duration,
   pub capacity: usize,
   pub shared_folder: String,
+  pub heartbeat_interval: Duration,
+  pub heartbeat_timeout: Duration,
+  pub symmetric: bool,
+  pub trace_export: Option<TraceExport>,
 }
 
 impl Options {
-  pub fn new(device: String) -> Self {
-    Self {
-      base_url: format!("http://{}:{}", HOST, PORT).into(),
-      token: read_token(),
-      workspace: "The Workspace".into(),
+  // Layers the config file, then SINNERGASM_* variables, then the flags.
+  // default_device is only used when no layer names the device.
+  pub fn load(args: &ClientArgs, default_device: &str) -> anyhow::Result<Self> {
+    let file = match args
+      .config
+      .clone()
+      .or_else(|| env_var("SINNERGASM_CLIENT_CONFIG").map(PathBuf::from))
+    {
+      Some(path) => ClientLayer::from_file(&path)?,
+      None if Path::new(DEFAULT_CONFIG_PATH).exists() => ClientLayer::from_file(Path::new(DEFAULT_CONFIG_PATH))?,
+      None => ClientLayer::default(),
+    };
+    let layer = file.merge(ClientLayer::from_env()?).merge(ClientLayer::from_args(args));
+
+    let token = match layer.token {
+      Some(token) => token.trim().to_string(),
+      None => read_token(&layer.token_file.unwrap_or_else(|| DEFAULT_TOKEN_PATH.into()))?,
+    };
+    anyhow::ensure!(!token.is_empty(), "The configured token is empty");
+
+    let device = layer.device.unwrap_or_else(|| default_device.into());
+    anyhow::ensure!(!device.is_empty(), "The device name must not be empty");
+
+    let shared_folder = match layer.shared_folder {
+      Some(shared_folder) => shared_folder,
+      None => std::env::current_dir()?
+        .join("upload_directory")
+        .to_string_lossy()
+        .into_owned(),
+    };
+
+    let heartbeat_interval = layer.heartbeat_interval.unwrap_or(5);
+    let heartbeat_timeout = layer.heartbeat_timeout.unwrap_or(20);
+    anyhow::ensure!(
+      heartbeat_interval > 0,
+      "The heartbeat interval must be at least one second"
+    );
+    anyhow::ensure!(
+      heartbeat_timeout > heartbeat_interval,
+      "The heartbeat timeout must be longer than the heartbeat interval"
+    );
+
+    let trace_export = match (layer.otlp_endpoint, layer.trace_file) {
+      (Some(_), Some(_)) => anyhow::bail!("Traces are exported to an OTLP endpoint or a file, not both"),
+      (Some(endpoint), None) => Some(TraceExport::Otlp(endpoint)),
+      (None, Some(path)) => Some(TraceExport::File(path)),
+      (None, None) => None,
+    };
+
+    Ok(Self {
+      base_url: layer.base_url.unwrap_or_else(|| format!("http://{}:{}", HOST, PORT)),
+      token,
+      workspace: layer.workspace.unwrap_or_else(|| "The Workspace".into()),
       device,
-      timeout: 5,
+      timeout: layer.timeout.unwrap_or(5),
       concurrency_limit: 256,
-      controller_mouse_frequency: Duration::from_millis(20),
+      controller_mouse_frequency: Duration::from_millis(layer.controller_mouse_frequency.unwrap_or(20)),
       capacity: 256,
-      shared_folder: "/work/ProjectsForFun/rust-synergy/seperate/upload_directory".into(),
+      shared_folder,
+      heartbeat_interval: Duration::from_secs(heartbeat_interval),
+      heartbeat_timeout: Duration::from_secs(heartbeat_timeout),
+      symmetric: layer.symmetric.unwrap_or(false),
+      trace_export,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+
+  // The environment is shared by every test in the process
+  static ENVIRONMENT: Mutex<()> = Mutex::new(());
+
+  const VARIABLES: [&str; 4] = [
+    "SINNERGASM_CLIENT_CONFIG",
+    "SINNERGASM_DEVICE",
+    "SINNERGASM_WORKSPACE",
+    "SINNERGASM_BASE_URL",
+  ];
+
+  // Loads the options with the config file and the variables given, nothing else from the environment
+  fn load(config: &str, variables: &[(&str, &str)], args: ClientArgs) -> Options {
+    let _environment = ENVIRONMENT.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    let path = std::env::temp_dir().join(format!("sinnergasm-options-{}.toml", std::process::id()));
+    std::fs::write(&path, config).unwrap();
+    for variable in VARIABLES {
+      std::env::remove_var(variable);
+    }
+    for (name, value) in variables {
+      std::env::set_var(name, value);
+    }
+    let options = Options::load(
+      &ClientArgs {
+        config: Some(path),
+        token: Some("secret".into()),
+        ..args
+      },
+      "default-device",
+    );
+    for (name, _) in variables {
+      std::env::remove_var(name);
     }
+    options.unwrap()
+  }
+
+  #[test]
+  fn flags_win_over_the_environment_which_wins_over_the_file() {
+    let config = "device = \"file-device\"\nworkspace = \"file-workspace\"\nbase_url = \"https://file\"";
+    let variables = [
+      ("SINNERGASM_DEVICE", "env-device"),
+      ("SINNERGASM_WORKSPACE", "env-workspace"),
+    ];
+    let options = load(
+      config,
+      &variables,
+      ClientArgs {
+        device: Some("flag-device".into()),
+        ..Default::default()
+      },
+    );
+
+    assert_eq!(options.device, "flag-device");
+    assert_eq!(options.workspace, "env-workspace");
+    assert_eq!(options.base_url, "https://file");
+    assert_eq!(options.token, "secret");
+  }
+
+  #[test]
+  fn the_default_device_is_only_used_when_no_layer_names_one() {
+    assert_eq!(load("", &[], ClientArgs::default()).device, "default-device");
+    assert_eq!(
+      load("device = \"file-device\"", &[], ClientArgs::default()).device,
+      "file-device"
+    );
+    assert_eq!(
+      load("", &[("SINNERGASM_DEVICE", "env-device")], ClientArgs::default()).device,
+      "env-device"
+    );
+    let args = ClientArgs {
+      device: Some("flag-device".into()),
+      ..Default::default()
+    };
+    assert_eq!(load("", &[], args).device, "flag-device");
   }
 }
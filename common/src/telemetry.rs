@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::propagation::Injector;
+use opentelemetry::sdk::export::trace::ExportResult;
+use opentelemetry::sdk::export::trace::SpanData;
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+use opentelemetry::sdk::trace as sdktrace;
+use opentelemetry::sdk::Resource;
+use opentelemetry::trace::SpanId;
+use opentelemetry::trace::TraceError;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use tonic::metadata::KeyRef;
+use tonic::metadata::MetadataKey;
+use tonic::metadata::MetadataMap;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::registry::LookupSpan;
+
+// Where the spans of the traces are sent, tracing is off when neither is configured
+#[derive(Clone, Debug)]
+pub enum TraceExport {
+  // The grpc endpoint of an OTLP collector, such as http://localhost:4317
+  Otlp(String),
+  // One json object per span, appended to the file
+  File(PathBuf),
+}
+
+// Exports the spans of this process as the given service, the one the collector groups them under.
+// The trace context is propagated in the w3c traceparent format.
+pub fn layer<S>(service: &str, export: &TraceExport) -> anyhow::Result<OpenTelemetryLayer<S, sdktrace::Tracer>>
+where
+  S: Subscriber + for<'span> LookupSpan<'span>,
+{
+  opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+  let config =
+    sdktrace::config().with_resource(Resource::new(vec![KeyValue::new("service.name", service.to_string())]));
+  let tracer = match export {
+    TraceExport::Otlp(endpoint) => opentelemetry_otlp::new_pipeline()
+      .tracing()
+      .with_exporter(
+        opentelemetry_otlp::new_exporter()
+          .tonic()
+          .with_endpoint(endpoint.clone()),
+      )
+      .with_trace_config(config)
+      .install_batch(opentelemetry::runtime::Tokio)
+      .with_context(|| format!("Unable to export traces to {}", endpoint))?,
+    TraceExport::File(path) => {
+      let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Unable to open trace file {:?}", path))?;
+      let provider = sdktrace::TracerProvider::builder()
+        .with_config(config)
+        .with_batch_exporter(FileExporter { file }, opentelemetry::runtime::Tokio)
+        .build();
+      let tracer = provider.tracer("sinnergasm");
+      opentelemetry::global::set_tracer_provider(provider);
+      tracer
+    }
+  };
+  Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+// Exports the spans that are still batched, spans ended after this are lost
+pub fn shutdown() {
+  opentelemetry::global::shutdown_tracer_provider();
+}
+
+// Adds the trace of the current span to an outgoing request
+pub fn inject(metadata: &mut MetadataMap) {
+  let context = tracing::Span::current().context();
+  opentelemetry::global::get_text_map_propagator(|propagator| {
+    propagator.inject_context(&context, &mut MetadataInjector(metadata))
+  });
+}
+
+// Makes the current span part of the trace the request was sent in
+pub fn continue_trace(metadata: &MetadataMap) {
+  let context =
+    opentelemetry::global::get_text_map_propagator(|propagator| propagator.extract(&MetadataExtractor(metadata)));
+  tracing::Span::current().set_parent(context);
+}
+
+// The trace of the current span, for messages sent down a stream that carry it themselves
+pub fn current_context() -> HashMap<String, String> {
+  let context = tracing::Span::current().context();
+  let mut carrier = HashMap::new();
+  opentelemetry::global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut carrier));
+  carrier
+}
+
+// Makes the span part of the trace a message carried
+pub fn follow(span: &tracing::Span, carrier: &HashMap<String, String>) {
+  if carrier.is_empty() {
+    return;
+  }
+  let context = opentelemetry::global::get_text_map_propagator(|propagator| propagator.extract(carrier));
+  span.set_parent(context);
+}
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl Injector for MetadataInjector<'_> {
+  fn set(&mut self, key: &str, value: String) {
+    if let (Ok(key), Ok(value)) = (MetadataKey::from_bytes(key.as_bytes()), value.parse()) {
+      self.0.insert(key, value);
+    }
+  }
+}
+
+struct MetadataExtractor<'a>(&'a MetadataMap);
+
+impl Extractor for MetadataExtractor<'_> {
+  fn get(&self, key: &str) -> Option<&str> {
+    self.0.get(key).and_then(|value| value.to_str().ok())
+  }
+
+  fn keys(&self) -> Vec<&str> {
+    self
+      .0
+      .keys()
+      .map(|key| match key {
+        KeyRef::Ascii(key) => key.as_str(),
+        KeyRef::Binary(key) => key.as_str(),
+      })
+      .collect()
+  }
+}
+
+// This version of the sdk has no exporter that writes to a file
+#[derive(Debug)]
+struct FileExporter {
+  file: std::fs::File,
+}
+
+impl FileExporter {
+  fn write(&mut self, batch: Vec<SpanData>) -> std::io::Result<()> {
+    let mut lines = vec![];
+    for span in batch {
+      serde_json::to_writer(&mut lines, &span_json(&span))?;
+      lines.push(b'\n');
+    }
+    // A single write keeps the lines of concurrent processes from interleaving
+    self.file.write_all(&lines)
+  }
+}
+
+impl SpanExporter for FileExporter {
+  fn export(&mut self, batch: Vec<SpanData>) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+    let result = self
+      .write(batch)
+      .map_err(|err| TraceError::from(format!("Unable to write spans: {}", err)));
+    Box::pin(std::future::ready(result))
+  }
+}
+
+fn span_json(span: &SpanData) -> serde_json::Value {
+  let attributes: serde_json::Map<String, serde_json::Value> = span
+    .attributes
+    .iter()
+    .map(|(key, value)| (key.as_str().to_string(), value.to_string().into()))
+    .collect();
+  serde_json::json!({
+    "trace_id": span.span_context.trace_id().to_string(),
+    "span_id": span.span_context.span_id().to_string(),
+    "parent_span_id": (span.parent_span_id != SpanId::INVALID).then(|| span.parent_span_id.to_string()),
+    "service": span.resource.get("service.name".into()).map(|service| service.to_string()),
+    "name": span.name,
+    "start_us": unix_us(span.start_time),
+    "duration_us": span.end_time.duration_since(span.start_time).unwrap_or_default().as_micros() as u64,
+    "attributes": attributes,
+  })
+}
+
+fn unix_us(time: SystemTime) -> u64 {
+  time
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_micros() as u64
+}
@@ -7,22 +7,30 @@ pub mod listener;
 pub mod options;
 // pub mod state;
 
-use crate::handler::configure_control_stream;
 use crate::handler::send_control_events;
+use crate::handler::ControlChannel;
+use crate::handler::Takeover;
 use crate::listener::listen_to_system;
-use sinnergasm::grpc_client::GrpcClient;
+use clap::Parser;
+use sinnergasm::options::ClientArgs;
 use sinnergasm::options::Options;
-use sinnergasm::protos as msg;
-use tokio::sync::mpsc as tokio_mpsc;
-use tokio_stream;
-use tokio_stream::wrappers::UnboundedReceiverStream;
 
 use sinnergasm::grpc_client::create_client;
+use sinnergasm::logging::init_logging;
+use sinnergasm::telemetry;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::Sender;
+use ui_common::clock::ServerClock;
 use ui_common::device_display::display_devices;
 use ui_common::events;
+use ui_common::heartbeat::launch_heartbeat_task;
+use ui_common::latency::launch_latency_reporter;
+use ui_common::latency::LatencyRecorder;
+use ui_common::membership::join_workspace;
+use ui_common::membership::leave_workspace;
+use ui_common::simulation::listen_to_client;
+use ui_common::simulation::simulate_receiver;
 use ui_common::subscribe::launch_subscription_task;
 use ui_common::target::launch_send_targets_task;
 
@@ -44,46 +52,68 @@ async fn flush_mouse_movements(
   }
 }
 
-fn launch_control_task(
-  client: GrpcClient,
-  control_recv: tokio_mpsc::UnboundedReceiver<msg::ControlRequest>,
-) -> tokio::task::JoinHandle<anyhow::Result<()>> {
-  let receiver_stream = UnboundedReceiverStream::new(control_recv);
-  let mut client_clone = client.clone();
-  let network_task = tokio::task::spawn(async move {
-    client_clone.control_workspace(receiver_stream).await?;
-    anyhow::Ok(())
-  });
-  return network_task;
-}
-
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-  let options = Arc::new(Options::new("desktop".into()));
-  let client = create_client(&options).await?;
+  let options = Arc::new(Options::load(&ClientArgs::parse(), "desktop")?);
+  init_logging("controller", options.trace_export.as_ref())?;
+  let mut client = create_client(&options).await?;
+  let workspace = join_workspace(&mut client, &options, true).await?;
 
   let (sender, _) = broadcast::channel::<events::AppEvent>(options.capacity);
 
-  let (control_send, control_recv) = tokio_mpsc::unbounded_channel();
-  let network_task = launch_control_task(client.clone(), control_recv);
-  configure_control_stream(&control_send, &options)?;
+  let clock = ServerClock::default();
+  let mut channel = ControlChannel::new(client.clone(), options.clone(), clock.clone());
+  // In symmetric mode the stream is only opened once this device has taken control
+  if !options.symmetric {
+    channel.connect()?;
+  }
+  let takeover = options.symmetric.then(|| {
+    Takeover::new(
+      options.device.clone(),
+      sender.clone(),
+      workspace.controller == options.device,
+    )
+  });
 
-  let subscribe_task = launch_subscription_task(options.clone(), client.clone(), sender.clone(), true).await;
+  let subscribe_task =
+    launch_subscription_task(options.clone(), client.clone(), sender.clone(), !options.symmetric).await;
   let target_task = launch_send_targets_task(sender.subscribe(), client.clone(), options.clone()).await;
+  let heartbeat_task = launch_heartbeat_task(options.clone(), client.clone(), clock.clone()).await;
 
   let sender_clone = sender.clone();
+  let symmetric = options.symmetric;
   let _ = std::thread::spawn(move || {
-    listen_to_system(sender_clone)?;
+    listen_to_system(sender_clone, symmetric)?;
     anyhow::Ok(())
   });
 
   let receiver = sender.subscribe();
   let forward_task = tokio::task::spawn(async move {
-    send_control_events(receiver, control_send).await?;
+    send_control_events(receiver, channel, takeover).await?;
     Ok(())
   });
 
+  let mut futures = vec![];
+  if options.symmetric {
+    // Symmetric mode also runs the simulator's side, this device can be targetted like any other
+    let sender_clone = sender.clone();
+    let client_clone = client.clone();
+    let options_clone = options.clone();
+    futures.push(tokio::task::spawn(async move {
+      listen_to_client(options_clone, client_clone, sender_clone).await?;
+      anyhow::Ok(())
+    }));
+
+    let latency = LatencyRecorder::new(&options);
+    futures.push(launch_latency_reporter(client.clone(), latency.clone()).await);
+    let receiver = sender.subscribe();
+    let clock = clock.clone();
+    futures.push(tokio::task::spawn(async move {
+      simulate_receiver(receiver, clock, latency).await?;
+      anyhow::Ok(())
+    }));
+  }
+
   let sender_clone = sender.clone();
   let frequency = options.controller_mouse_frequency;
   let flush_task = tokio::task::spawn(async move {
@@ -97,26 +127,30 @@ async fn main() -> anyhow::Result<()> {
   let options_clone = options.clone();
   let upload_task = tokio::spawn(async move {
     if let Err(err) = ui_common::upload::listen_for_uploads(receiver, client_clone, options_clone).await {
-      eprintln!("Error listening for uploads: {}", err);
+      tracing::error!("Error listening for uploads: {}", err);
     }
     anyhow::Ok(())
   });
 
-  display_devices(client, &options, sender).await?;
+  display_devices(client.clone(), &options, sender).await?;
 
-  println!("Display closed");
+  tracing::info!("Display closed");
+  if let Err(err) = leave_workspace(&mut client, &options).await {
+    tracing::error!("Unable to leave the workspace: {}", err);
+  }
+  telemetry::shutdown();
 
   // TODO: cleanly close the connections...
   die_early();
 
-  let futures = vec![
+  futures.extend([
     forward_task,
     target_task,
     subscribe_task,
-    network_task,
     flush_task,
     upload_task,
-  ];
+    heartbeat_task,
+  ]);
   futures::future::join_all(futures).await;
 
   anyhow::Ok(())
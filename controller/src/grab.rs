@@ -65,7 +65,7 @@ fn test () {
 #[cfg(feature = "unstable_grab")]
 let callback = |event: Event| -> Option<Event> {
     if let EventType::KeyPress(Key::CapsLock) = event.event_type {
-        println!("Consuming and cancelling CapsLock");
+        tracing::debug!("Consuming and cancelling CapsLock");
         None  // CapsLock is now effectively disabled
     } else {
       Some(event)
@@ -74,6 +74,6 @@ let callback = |event: Event| -> Option<Event> {
 // This will block.
 #[cfg(feature = "unstable_grab")]
 if let Err(error) = grab(callback) {
-    println!("Error: {:?}", error)
+    tracing::error!("Error: {:?}", error)
 }
 }
\ No newline at end of file
@@ -1,17 +1,30 @@
 use anyhow;
 use rdev;
+use sinnergasm::grpc_client::GrpcClient;
 use sinnergasm::options::Options;
 use sinnergasm::protos as msg;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 use tokio::sync::broadcast::Receiver;
+use tokio::sync::broadcast::Sender;
 use tokio::sync::mpsc as tokio_mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio_stream::wrappers::ReceiverStream;
+use ui_common::clock::ServerClock;
 use ui_common::events;
 use ui_common::translation as tr;
 
-pub(crate) fn configure_control_stream(
-  control_sender: &tokio_mpsc::UnboundedSender<msg::ControlRequest>,
+// Input right after this device stops being the target or controller may still be simulated
+const TAKEOVER_GRACE: Duration = Duration::from_millis(300);
+// How long to wait for a take control request before sending another one
+const TAKEOVER_RETRY: Duration = Duration::from_secs(1);
+
+fn configure_control_stream(
+  control_sender: &tokio_mpsc::Sender<msg::ControlRequest>,
   options: &Options,
 ) -> Result<(), anyhow::Error> {
-  control_sender.send(msg::ControlRequest {
+  control_sender.try_send(msg::ControlRequest {
     event_type: Some(msg::control_request::EventType::Workspace(msg::ControlWorkspace {
       workspace: options.workspace.clone(),
       device: options.device.clone(),
@@ -20,37 +33,136 @@ pub(crate) fn configure_control_stream(
   Ok(())
 }
 
-fn translate_other_events(event: rdev::EventType) -> msg::ControlRequest {
-  msg::ControlRequest {
-    event_type: Some(msg::control_request::EventType::InputEvent(msg::UserInputEvent {
-      r#type: Some(match event {
-        rdev::EventType::KeyPress(key) => msg::user_input_event::Type::KeyPress(tr::rdev_to_msg(&key)),
-        rdev::EventType::KeyRelease(key) => msg::user_input_event::Type::KeyRelease(tr::rdev_to_msg(&key)),
-        rdev::EventType::ButtonPress(button) => msg::user_input_event::Type::MousePress(tr::mouse_rdev_to_msg(button)),
-        rdev::EventType::ButtonRelease(button) => {
-          msg::user_input_event::Type::MouseRelease(tr::mouse_rdev_to_msg(button))
-        }
-        rdev::EventType::Wheel { delta_x, delta_y } => msg::user_input_event::Type::Wheel(msg::WheelEvent {
-          dx: delta_x as i32,
-          dy: delta_y as i32,
-        }),
-        rdev::EventType::MouseMove { x: _, y: _ } => panic!("Handled seperately"),
-      }),
-    })),
-  }
+// The server ends the control stream when control is handed to another device,
+// so a closed stream is opened again for the next event.
+// Input waits for room on a full stream, except mouse moves which are merged into the next one.
+pub(crate) struct ControlChannel {
+  client: GrpcClient,
+  options: Arc<Options>,
+  clock: ServerClock,
+  sender: Option<tokio_mpsc::Sender<msg::ControlRequest>>,
+  // The sequence number of the last input sent on the stream
+  sequence: u64,
 }
 
-fn mouse_move_event(delta_x: f64, delta_y: f64) -> msg::ControlRequest {
-  msg::ControlRequest {
-    event_type: Some(msg::control_request::EventType::InputEvent(msg::UserInputEvent {
-      r#type: Some(msg::user_input_event::Type::MouseMove(msg::MouseMoveEvent {
-        delta_x,
-        delta_y,
+impl ControlChannel {
+  pub(crate) fn new(client: GrpcClient, options: Arc<Options>, clock: ServerClock) -> Self {
+    Self {
+      client,
+      options,
+      clock,
+      sender: None,
+      sequence: 0,
+    }
+  }
+
+  pub(crate) fn connect(&mut self) -> Result<(), anyhow::Error> {
+    let (sender, receiver) = tokio_mpsc::channel(self.options.capacity);
+    configure_control_stream(&sender, &self.options)?;
+    let mut client = self.client.clone();
+    tokio::task::spawn(async move {
+      if let Err(err) = client.control_workspace(ReceiverStream::new(receiver)).await {
+        tracing::error!("Control stream closed: {}", err);
+      }
+    });
+    self.sender = Some(sender);
+    self.sequence = 0;
+    Ok(())
+  }
+
+  // Input is stamped on the server clock, so the simulator can tell how long it took to arrive
+  fn now_us(&self) -> u64 {
+    self.clock.now_us()
+  }
+
+  // False when a mouse move did not fit on the stream and has to be sent again with the next
+  async fn send(&mut self, input: msg::user_input_event::Type, captured_at_us: u64) -> Result<bool, anyhow::Error> {
+    if !matches!(&self.sender, Some(sender) if !sender.is_closed()) {
+      self.connect()?;
+    }
+    let Some(sender) = self.sender.as_ref() else {
+      return Ok(false);
+    };
+    let mouse_move = matches!(input, msg::user_input_event::Type::MouseMove(_));
+    let request = msg::ControlRequest {
+      event_type: Some(msg::control_request::EventType::InputEvent(msg::UserInputEvent {
+        r#type: Some(input),
+        sequence: self.sequence + 1,
+        captured_at_us,
       })),
-    })),
+    };
+    if mouse_move {
+      match sender.try_send(request) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => return Ok(false),
+        Err(err) => return Err(err.into()),
+      }
+    } else {
+      sender.send(request).await?;
+    }
+    self.sequence += 1;
+    Ok(true)
   }
 }
 
+// Symmetric mode: local input on a device that is neither the target nor forwarding takes control
+pub(crate) struct Takeover {
+  device: String,
+  sender: Sender<events::AppEvent>,
+  // Whether this device holds control of the workspace
+  controlling: bool,
+  targetted: bool,
+  ignore_until: Instant,
+}
+
+impl Takeover {
+  pub(crate) fn new(device: String, sender: Sender<events::AppEvent>, controlling: bool) -> Self {
+    Self {
+      device,
+      sender,
+      controlling,
+      targetted: false,
+      ignore_until: Instant::now(),
+    }
+  }
+
+  fn become_idle(&mut self) {
+    self.ignore_until = Instant::now() + TAKEOVER_GRACE;
+  }
+
+  fn local_input(&mut self) {
+    let now = Instant::now();
+    if self.targetted || now < self.ignore_until {
+      return;
+    }
+    tracing::info!("Local input detected, taking control");
+    self.ignore_until = now + TAKEOVER_RETRY;
+    if let Err(err) = self.sender.send(events::AppEvent::SubscriptionEvent(
+      events::SubscriptionEvent::TakeControl,
+    )) {
+      tracing::error!("Error requesting control: {}", err);
+    }
+  }
+}
+
+fn translate_other_events(event: rdev::EventType) -> msg::user_input_event::Type {
+  match event {
+    rdev::EventType::KeyPress(key) => msg::user_input_event::Type::KeyPress(tr::rdev_to_msg(&key)),
+    rdev::EventType::KeyRelease(key) => msg::user_input_event::Type::KeyRelease(tr::rdev_to_msg(&key)),
+    rdev::EventType::ButtonPress(button) => msg::user_input_event::Type::MousePress(tr::mouse_rdev_to_msg(button)),
+    rdev::EventType::ButtonRelease(button) => msg::user_input_event::Type::MouseRelease(tr::mouse_rdev_to_msg(button)),
+    rdev::EventType::Wheel { delta_x, delta_y } => msg::user_input_event::Type::Wheel(msg::WheelEvent {
+      dx: delta_x as i32,
+      dy: delta_y as i32,
+    }),
+    rdev::EventType::MouseMove { x: _, y: _ } => panic!("Handled seperately"),
+  }
+}
+
+fn mouse_move_event(delta_x: f64, delta_y: f64) -> msg::user_input_event::Type {
+  msg::user_input_event::Type::MouseMove(msg::MouseMoveEvent { delta_x, delta_y })
+}
+
 // // TODO: Is this still needed?
 // tokio::task::yield_now().await;
 
@@ -62,6 +174,8 @@ struct ForwardState {
   virtual_location: (f64, f64),
   // The last virtual location we sent to the server
   sent_location: (f64, f64),
+  // When the first movement that has not been sent yet was captured
+  pending_since_us: Option<u64>,
 }
 
 impl ForwardState {
@@ -71,6 +185,7 @@ impl ForwardState {
       // mouse_location: initial_location,
       virtual_location: (0.0, 0.0),
       sent_location: (0.0, 0.0),
+      pending_since_us: None,
     }
   }
 
@@ -84,14 +199,15 @@ impl ForwardState {
       x: self.initial_location.0,
       y: self.initial_location.1,
     }) {
-      println!("Failed to simulate mouse move: {:?}", err);
+      tracing::warn!("Failed to simulate mouse move: {:?}", err);
     }
   }
 
-  fn update(&mut self, last: (f64, f64), next: (f64, f64)) {
+  fn update(&mut self, last: (f64, f64), next: (f64, f64), captured_at_us: u64) {
     if self.is_simulated_input(next) {
       return;
     }
+    self.pending_since_us.get_or_insert(captured_at_us);
     let delta_x = next.0 - last.0;
     let delta_y = next.1 - last.1;
 
@@ -101,24 +217,37 @@ impl ForwardState {
     self.return_to_initial_position();
   }
 
-  fn maybe_send(&mut self, sender: &tokio_mpsc::UnboundedSender<msg::ControlRequest>) {
+  async fn maybe_send(&mut self, channel: &mut ControlChannel) {
     if self.virtual_location == self.sent_location {
+      // Movements that cancelled each other out are not sent
+      self.pending_since_us = None;
       return;
     }
 
     let delta_x = self.virtual_location.0 - self.sent_location.0;
     let delta_y = self.virtual_location.1 - self.sent_location.1;
-    if let Err(err) = sender.send(mouse_move_event(delta_x, delta_y)) {
-      eprintln!("Error sending mouse move message: {}", err);
+    // Counting from the oldest movement shows how much the flush interval adds
+    let captured_at_us = self.pending_since_us.unwrap_or_else(|| channel.now_us());
+    match channel.send(mouse_move_event(delta_x, delta_y), captured_at_us).await {
+      Ok(false) => {
+        tracing::debug!("Control stream is full, merging the mouse move into the next one");
+        self.pending_since_us = Some(captured_at_us);
+        return;
+      }
+      Ok(true) => {}
+      Err(err) => tracing::error!("Error sending mouse move message: {}", err),
     }
 
+    self.pending_since_us = None;
     self.sent_location = self.virtual_location;
   }
 }
 
-pub async fn send_control_events(
+pub(crate) async fn send_control_events(
   mut receiver: Receiver<events::AppEvent>,
-  sender: tokio_mpsc::UnboundedSender<msg::ControlRequest>,
+  mut channel: ControlChannel,
+  // Only set in symmetric mode
+  mut takeover: Option<Takeover>,
 ) -> Result<(), anyhow::Error> {
   let mut forward_state = Option::<ForwardState>::None;
   let mut last_position = Option::<(f64, f64)>::None;
@@ -126,46 +255,70 @@ pub async fn send_control_events(
   loop {
     match receiver.recv().await? {
       events::AppEvent::Quit => {
-        println!("Received quit event");
+        tracing::info!("Received quit event");
         return Ok(());
       }
       events::AppEvent::ControlEvent(events::ControllerEvent::RDevEvent(rdev::EventType::MouseMove { x, y })) => {
         if let Some(state) = forward_state.as_mut() {
           let next = (x, y);
           let last = last_position.expect("No last position found");
-          state.update(last, next);
+          state.update(last, next, channel.now_us());
+        } else if let Some(takeover) = takeover.as_mut() {
+          takeover.local_input();
         }
         last_position = Some((x, y));
       }
       events::AppEvent::ControlEvent(events::ControllerEvent::RDevEvent(rdev_event)) => {
         if let Some(state) = forward_state.as_mut() {
+          let captured_at_us = channel.now_us();
           // Flush mouse location before other events...
-          state.maybe_send(&sender);
+          state.maybe_send(&mut channel).await;
 
-          if let Err(err) = sender.send(translate_other_events(rdev_event)) {
-            eprintln!("Error sending message: {}", err);
+          if let Err(err) = channel.send(translate_other_events(rdev_event), captured_at_us).await {
+            tracing::error!("Error sending message: {}", err);
           }
+        } else if let Some(takeover) = takeover.as_mut() {
+          takeover.local_input();
         }
       }
       events::AppEvent::ControlEvent(events::ControllerEvent::FlushMouse) => {
         if let Some(state) = forward_state.as_mut() {
-          state.maybe_send(&sender);
+          state.maybe_send(&mut channel).await;
         }
       }
       events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::Targetted) => {
-        println!("Not fowarding events");
+        tracing::info!("Not fowarding events");
         forward_state = None;
+        if let Some(takeover) = takeover.as_mut() {
+          takeover.targetted = true;
+        }
       }
       events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::Untargetted) => {
+        if let Some(takeover) = takeover.as_mut() {
+          takeover.targetted = false;
+          takeover.become_idle();
+          if !takeover.controlling {
+            continue;
+          }
+        }
         if let Some(last) = last_position {
           // Move it out of the way of the go to laptop button...
           let last = (last.0, last.1 + 50.0);
           rdev::simulate(&rdev::EventType::MouseMove { x: last.0, y: last.1 })?;
 
           forward_state = Some(ForwardState::new(last));
-          println!("Starting fowarding events");
+          tracing::info!("Starting fowarding events");
         } else {
-          println!("No mouse position found, ignoring listen event");
+          tracing::warn!("No mouse position found, ignoring listen event");
+        }
+      }
+      events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::ConfigurationUpdate(workspace)) => {
+        if let Some(takeover) = takeover.as_mut() {
+          takeover.controlling = workspace.controller == takeover.device;
+          if !takeover.controlling && forward_state.take().is_some() {
+            tracing::info!("Control moved to {}, not forwarding events", workspace.controller);
+            takeover.become_idle();
+          }
         }
       }
       _ => {}
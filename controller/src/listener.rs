@@ -5,15 +5,23 @@ use ui_common::events;
 
 use tokio::sync::broadcast::Sender;
 
-pub(crate) fn listen_to_system(sender: Sender<events::AppEvent>) -> Result<(), RDevError> {
+pub(crate) fn listen_to_system(sender: Sender<events::AppEvent>, symmetric: bool) -> Result<(), RDevError> {
   rdev::listen(move |event| {
     if matches!(event.event_type, rdev::EventType::KeyPress(rdev::Key::AltGr)) {
       panic!("Need some escape key: AltGr pressed");
     }
+    // Only one listener can run, so it also feeds the simulation side in symmetric mode
+    if let (true, rdev::EventType::MouseMove { x, y }) = (symmetric, event.event_type) {
+      if let Err(e) = sender.send(events::AppEvent::SimulationEvent(
+        events::SimulationEvent::LocalMouseChanged(x, y),
+      )) {
+        tracing::error!("Error sending rdev event: {:?}", e);
+      }
+    }
     if let Err(e) = sender.send(events::AppEvent::ControlEvent(events::ControllerEvent::RDevEvent(
       event.event_type,
     ))) {
-      eprintln!("Error sending rdev event: {:?}", e);
+      tracing::error!("Error sending rdev event: {:?}", e);
     }
   })?;
   Ok(())
@@ -32,14 +32,14 @@ impl MouseParoleOfficer {
   }
 
   pub(crate) fn get_delta(&mut self, (x, y): (f64, f64)) -> Option<(f64, f64)> {
-    println!("State: {:?} received event {:?}", self, (x, y));
+    tracing::trace!("State: {:?} received event {:?}", self, (x, y));
     if x == self.initial_position.0 && y == self.initial_position.1 {
-      println!("Ignoring event back to initial position");
+      tracing::trace!("Ignoring event back to initial position");
       // Ignore the events we create
       self.last_position = (x, y);
       return None;
     } else {
-      println!("Event not back to initial position, must be generated by user.");
+      tracing::trace!("Event not back to initial position, must be generated by user.");
     }
 
     let delta_x = x - self.last_position.0;
@@ -53,7 +53,7 @@ impl MouseParoleOfficer {
       x: self.initial_position.0,
       y: self.initial_position.1,
     }) {
-      println!("Failed to simulate mouse move: {:?}", err);
+      tracing::warn!("Failed to simulate mouse move: {:?}", err);
     }
     Some(message)
   }
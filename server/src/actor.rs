@@ -1,74 +1,283 @@
-use std::collections::BTreeMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
-use crate::common as ids;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tracing::Instrument;
 
-pub(crate) enum SubscriptionEvent<T> {
-  ListenerCreated(ids::WorkspaceName, ids::DeviceName, tokio::sync::mpsc::UnboundedSender<T>),
-  ListenerRemoved(ids::WorkspaceName, ids::DeviceName),
-  WorskpaceClosed(ids::WorkspaceName),
-  Event(ids::WorkspaceName, T),
-  ApplicationClosing,
+use crate::metrics::Metrics;
+
+// An actor that panics more often than this within the window is not restarted again
+const MAXIMUM_RESTARTS: usize = 5;
+const RESTART_WINDOW: Duration = Duration::from_secs(60);
+
+pub(crate) trait Actor: Send + 'static {
+  type Message: Send + 'static;
+
+  fn receive(&mut self, message: Self::Message);
+
+  // The span the message is handled in, so what the actor logs is keyed by the workspace or device it is about
+  fn span(_message: &Self::Message) -> tracing::Span {
+    tracing::Span::none()
+  }
+
+  // Called once every message sent before the actor was stopped has been handled
+  fn stopped(&mut self) {}
+}
+
+// Messages about a whole workspace
+pub(crate) fn workspace_span(workspace_name: &str) -> tracing::Span {
+  tracing::info_span!("workspace", workspace = workspace_name)
 }
 
+// Messages about one device in a workspace
+pub(crate) fn device_span(workspace_name: &str, device_name: &str) -> tracing::Span {
+  tracing::info_span!("device", workspace = workspace_name, device = device_name)
+}
 
 #[derive(Debug)]
-pub(crate) struct Actor<T> {
-  listeners: BTreeMap<
-    ids::WorkspaceName,
-    BTreeMap<ids::DeviceName, tokio::sync::mpsc::UnboundedSender<T>>,
-  >,
+pub(crate) enum ActorError {
+  Stopped(&'static str),
+  NoReply(&'static str),
 }
 
-impl<T> Actor<T> 
-where
-  T: Clone + Send + 'static,
-  {
-  pub(crate) fn new() -> Self {
+impl std::fmt::Display for ActorError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      ActorError::Stopped(name) => write!(f, "The {} actor has stopped", name),
+      ActorError::NoReply(name) => write!(f, "The {} actor did not reply", name),
+    }
+  }
+}
+
+impl std::error::Error for ActorError {}
+
+impl From<ActorError> for tonic::Status {
+  fn from(err: ActorError) -> Self {
+    tonic::Status::unavailable(err.to_string())
+  }
+}
+
+enum Envelope<M> {
+  // With the span it was sent from, so handling it stays part of the sender's trace
+  Message(M, tracing::Span),
+  Stop,
+}
+
+pub(crate) struct ActorHandle<M> {
+  name: &'static str,
+  sender: mpsc::UnboundedSender<Envelope<M>>,
+  queue_depth: prometheus::IntGauge,
+  metrics: Arc<Metrics>,
+}
+
+impl<M> Clone for ActorHandle<M> {
+  fn clone(&self) -> Self {
     Self {
-      listeners: BTreeMap::new(),
+      name: self.name,
+      sender: self.sender.clone(),
+      queue_depth: self.queue_depth.clone(),
+      metrics: self.metrics.clone(),
     }
   }
+}
+
+impl<M> std::fmt::Debug for ActorHandle<M> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ActorHandle").field("name", &self.name).finish()
+  }
+}
+
+impl<M> ActorHandle<M> {
+  pub(crate) fn send(&self, message: M) -> Result<(), ActorError> {
+    // Counted before it is sent, the actor may handle it before send returns
+    self.queue_depth.inc();
+    if self
+      .sender
+      .send(Envelope::Message(message, tracing::Span::current()))
+      .is_err()
+    {
+      self.queue_depth.dec();
+      self.metrics.dropped(self.name);
+      return Err(ActorError::Stopped(self.name));
+    }
+    Ok(())
+  }
+
+  // False once the actor has stopped, for good when it kept panicking
+  pub(crate) fn is_alive(&self) -> bool {
+    !self.sender.is_closed()
+  }
+
+  // Sends a message that carries its own reply channel and waits for the reply
+  pub(crate) async fn request<R>(&self, message: impl FnOnce(oneshot::Sender<R>) -> M) -> Result<R, ActorError> {
+    let (reply, response) = oneshot::channel();
+    self.send(message(reply))?;
+    response.await.map_err(|_| ActorError::NoReply(self.name))
+  }
+}
+
+struct RunningActor {
+  name: &'static str,
+  stop: Box<dyn FnOnce() + Send>,
+  task: tokio::task::JoinHandle<()>,
+}
 
-  pub(crate) fn receive(
+// Runs the actors of the server, restarting the ones that panic
+pub(crate) struct ActorSystem {
+  running: Vec<RunningActor>,
+  metrics: Arc<Metrics>,
+}
+
+impl ActorSystem {
+  pub(crate) fn new(metrics: Arc<Metrics>) -> Self {
+    Self {
+      running: vec![],
+      metrics,
+    }
+  }
+
+  // The factory creates the actor again after it panics, so the state it held is lost
+  pub(crate) fn spawn<A: Actor>(
     &mut self,
-    event: SubscriptionEvent<T>,
-  ) {
-    match event {
-      SubscriptionEvent::ListenerCreated(
-        workspace_name, device_name, sender
-      ) => {
-        self
-          .listeners
-          .entry(workspace_name)
-          .or_insert_with(BTreeMap::new)
-          .insert(device_name, sender);
-      },
-      SubscriptionEvent::ListenerRemoved(workspace_id, device_id) => {
-        if let Some(device_map) = self.listeners.get_mut(&workspace_id) {
-          device_map.remove(&device_id);
-          // Do this in the workspace actor...
-          if device_map.is_empty() {
-            self.listeners.remove(&workspace_id);
+    name: &'static str,
+    factory: impl Fn() -> A + Send + 'static,
+  ) -> ActorHandle<A::Message> {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<Envelope<A::Message>>();
+    let queue_depth = self.metrics.queue_depth.with_label_values(&[name]);
+    let actor_queue_depth = queue_depth.clone();
+    let task = async move {
+      let mut actor = factory();
+      let mut restarts = Vec::<Instant>::new();
+      while let Some(envelope) = receiver.recv().await {
+        let (message, sent_from) = match envelope {
+          Envelope::Message(message, sent_from) => {
+            actor_queue_depth.dec();
+            (message, sent_from)
+          }
+          Envelope::Stop => {
+            actor.stopped();
+            break;
           }
+        };
+        // Sent from outside any span the message is handled under the actor's own span
+        let span = sent_from.in_scope(|| A::span(&message));
+        if std::panic::catch_unwind(AssertUnwindSafe(|| span.in_scope(|| actor.receive(message)))).is_ok() {
+          continue;
+        }
+
+        restarts.retain(|restart| restart.elapsed() < RESTART_WINDOW);
+        if restarts.len() >= MAXIMUM_RESTARTS {
+          tracing::error!("The actor keeps panicking, it will not be restarted");
+          break;
         }
-      },
-      SubscriptionEvent::WorskpaceClosed(workspace_name) => {
-        self.listeners.remove(&workspace_name);
-      },
-      SubscriptionEvent::Event(workspace_name, event) => {
-        if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
-          device_map.retain(|_, listener| {
-            if let Err(err) = listener.send(event.clone()) {
-              tracing::info!("Failed to send event to listener: {:?}", err);
-              return false;
-            }
-            return true;
-          });
+        restarts.push(Instant::now());
+        tracing::error!("The actor panicked, restarting it");
+        actor = factory();
+      }
+      // Whatever is still queued is dropped with the receiver
+      actor_queue_depth.set(0);
+    }
+    .instrument(tracing::info_span!("actor", actor = name));
+    let task = tokio::task::spawn(task);
+
+    let stop_sender = sender.clone();
+    self.running.push(RunningActor {
+      name,
+      stop: Box::new(move || {
+        // The actor may already have stopped on its own
+        let _ = stop_sender.send(Envelope::Stop);
+      }),
+      task,
+    });
+    ActorHandle {
+      name,
+      sender,
+      queue_depth,
+      metrics: self.metrics.clone(),
+    }
+  }
+
+  // Stops the actors in the reverse order they were spawned in, so an actor can
+  // still reach the ones it depends on while it handles its last messages
+  pub(crate) async fn shutdown(self) {
+    for actor in self.running.into_iter().rev() {
+      (actor.stop)();
+      if let Err(err) = actor.task.await {
+        tracing::error!(actor = actor.name, "The actor did not stop cleanly: {}", err);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+
+  enum Message {
+    Panic,
+    // Replies with how many times the actor has been created
+    Generation(oneshot::Sender<usize>),
+  }
+
+  struct PanickingActor {
+    generation: usize,
+  }
+
+  impl Actor for PanickingActor {
+    type Message = Message;
+
+    fn receive(&mut self, message: Message) {
+      match message {
+        Message::Panic => panic!("Asked to panic"),
+        Message::Generation(reply) => {
+          let _ = reply.send(self.generation);
         }
-      },
-      SubscriptionEvent::ApplicationClosing => {
-        self.listeners.clear();
-      },
+      }
     }
   }
+
+  fn spawn_panicking(actors: &mut ActorSystem) -> ActorHandle<Message> {
+    let created = Arc::new(AtomicUsize::new(0));
+    actors.spawn("panicking", move || PanickingActor {
+      generation: created.fetch_add(1, Ordering::SeqCst) + 1,
+    })
+  }
+
+  #[tokio::test]
+  async fn restarts_an_actor_that_panics() {
+    let mut actors = ActorSystem::new(Arc::new(Metrics::new().unwrap()));
+    let handle = spawn_panicking(&mut actors);
+
+    assert_eq!(handle.request(Message::Generation).await.unwrap(), 1);
+    handle.send(Message::Panic).unwrap();
+    assert_eq!(handle.request(Message::Generation).await.unwrap(), 2);
+    assert!(handle.is_alive());
+    actors.shutdown().await;
+  }
+
+  #[tokio::test]
+  async fn gives_up_on_an_actor_that_keeps_panicking() {
+    let mut actors = ActorSystem::new(Arc::new(Metrics::new().unwrap()));
+    let handle = spawn_panicking(&mut actors);
+
+    for _ in 0..MAXIMUM_RESTARTS {
+      handle.send(Message::Panic).unwrap();
+    }
+    assert_eq!(handle.request(Message::Generation).await.unwrap(), MAXIMUM_RESTARTS + 1);
+
+    // One more within the window stops the actor, what was queued behind it is dropped
+    handle.send(Message::Panic).unwrap();
+    assert!(matches!(
+      handle.request(Message::Generation).await,
+      Err(ActorError::NoReply(_)) | Err(ActorError::Stopped(_))
+    ));
+    assert!(!handle.is_alive());
+    assert!(matches!(handle.send(Message::Panic), Err(ActorError::Stopped(_))));
+    actors.shutdown().await;
+  }
 }
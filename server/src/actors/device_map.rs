@@ -4,33 +4,96 @@ use crate::common as ids;
 
 use std::fmt::Debug;
 use std::fmt::Formatter;
+use std::time::Duration;
+use std::time::Instant;
 use tokio::sync::mpsc as tokio_mpsc;
 
+// The sending half of the stream to a device
+pub(crate) trait StreamSender: Sized {
+  // Does not keep the stream open
+  type Weak;
+
+  fn same_channel(&self, other: &Self) -> bool;
+  fn downgrade(&self) -> Self::Weak;
+  fn upgrade(weak: &Self::Weak) -> Option<Self>;
+}
+
+impl<T> StreamSender for tokio_mpsc::UnboundedSender<T> {
+  type Weak = tokio_mpsc::WeakUnboundedSender<T>;
+
+  fn same_channel(&self, other: &Self) -> bool {
+    tokio_mpsc::UnboundedSender::same_channel(self, other)
+  }
+
+  fn downgrade(&self) -> Self::Weak {
+    tokio_mpsc::UnboundedSender::downgrade(self)
+  }
+
+  fn upgrade(weak: &Self::Weak) -> Option<Self> {
+    weak.upgrade()
+  }
+}
+
 // #[derive(Default)]
-pub(crate) struct DeviceMap<T> {
-  pub(crate) target: Option<(ids::DeviceName, tokio_mpsc::UnboundedSender<T>)>,
-  pub(crate) devices: BTreeMap<ids::DeviceName, tokio_mpsc::UnboundedSender<T>>,
+pub(crate) struct DeviceMap<S> {
+  pub(crate) target: Option<(ids::DeviceName, S)>,
+  pub(crate) devices: BTreeMap<ids::DeviceName, S>,
+  // When each device last sent a heartbeat
+  last_seen: BTreeMap<ids::DeviceName, Instant>,
 }
 
-impl<T> DeviceMap<T> {
+impl<S: StreamSender> DeviceMap<S> {
+  pub(crate) fn insert(&mut self, device: ids::DeviceName, sender: S) {
+    self.last_seen.insert(device.clone(), Instant::now());
+    self.devices.insert(device, sender);
+  }
+  pub(crate) fn seen(&mut self, device: &ids::DeviceName) {
+    if self.devices.contains_key(device) {
+      self.last_seen.insert(device.clone(), Instant::now());
+    }
+  }
+  // Devices that have not sent a heartbeat within the timeout
+  pub(crate) fn expired(&mut self, timeout: Duration) -> Vec<ids::DeviceName> {
+    let devices = &self.devices;
+    self.last_seen.retain(|device, _| devices.contains_key(device));
+    self
+      .last_seen
+      .iter()
+      .filter(|(_, last_seen)| last_seen.elapsed() > timeout)
+      .map(|(device, _)| device.clone())
+      .collect()
+  }
   pub(crate) fn remove(&mut self, device: &ids::DeviceName) {
     self.devices.remove(device);
+    self.last_seen.remove(device);
+    if matches!(&self.target, Some((target, _)) if target == device) {
+      self.target = None;
+    }
+  }
+  // Only removes the device if it is still registered with this sender, it may have reconnected since
+  pub(crate) fn remove_channel(&mut self, device: &ids::DeviceName, sender: &S) -> bool {
+    if !matches!(self.devices.get(device), Some(existing) if existing.same_channel(sender)) {
+      return false;
+    }
+    self.remove(device);
+    true
   }
   pub(crate) fn is_empty(&self) -> bool {
     self.devices.is_empty()
   }
 }
 
-impl<T> Default for DeviceMap<T> {
+impl<S> Default for DeviceMap<S> {
   fn default() -> Self {
     Self {
       target: None,
       devices: BTreeMap::new(),
+      last_seen: BTreeMap::new(),
     }
   }
 }
 
-impl<T> Debug for DeviceMap<T> {
+impl<S> Debug for DeviceMap<S> {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     f.debug_struct("DeviceMap")
       .field("target", &self.target.as_ref().map(|(name, _)| name))
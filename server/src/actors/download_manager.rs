@@ -1,8 +1,14 @@
 use std::collections::BTreeMap;
+use std::sync::Arc;
 
 use tokio::sync::mpsc::UnboundedSender as Sender;
+use tokio::sync::oneshot;
 
+use crate::actor::device_span;
+use crate::actor::workspace_span;
+use crate::actor::Actor;
 use crate::common as ids;
+use crate::metrics::Metrics;
 use sinnergasm::protos as msg;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -30,6 +36,23 @@ impl DownloadKey {
       relative_path: initiate.relative_path.clone(),
     }
   }
+  // Everything logged about the transfer is keyed by both devices and the file
+  pub(crate) fn span(&self) -> tracing::Span {
+    tracing::info_span!(
+      "transfer",
+      workspace = %self.workspace,
+      downloader = %self.download_device,
+      uploader = %self.upload_device,
+      path = %self.relative_path
+    )
+  }
+  // Fills in a span that declared the same fields but did not know the transfer yet
+  pub(crate) fn record(&self, span: &tracing::Span) {
+    span.record("workspace", self.workspace.as_str());
+    span.record("downloader", self.download_device.as_str());
+    span.record("uploader", self.upload_device.as_str());
+    span.record("path", self.relative_path.as_str());
+  }
 }
 
 pub(crate) enum DownloadEvent {
@@ -39,7 +62,9 @@ pub(crate) enum DownloadEvent {
   SendFileChunk(DownloadKey, msg::SharedFileChunk),
   DownloadComplete(DownloadKey),
   WorkspaceClosing(ids::WorkspaceName),
-  ApplicationClosing,
+  DeviceLeaving(ids::WorkspaceName, ids::DeviceName),
+  // Replies once the running transfers have finished, new transfers are refused from then on
+  Drain(oneshot::Sender<()>),
   // DownloadInitiated(ids::WorkspaceName, ids::DeviceName, ids::SharedFileId),
   // Subscribe(
   //   ids::WorkspaceName,
@@ -72,13 +97,29 @@ impl DownloadConnection {
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(crate) struct DownloadsActor {
   connections: BTreeMap<DownloadKey, DownloadConnection>,
+  draining: bool,
+  drained: Option<oneshot::Sender<()>>,
+  metrics: Arc<Metrics>,
 }
 
 impl DownloadsActor {
-  pub(crate) fn receive(&mut self, download_event: DownloadEvent) {
+  pub(crate) fn new(metrics: Arc<Metrics>) -> Self {
+    Self {
+      connections: BTreeMap::new(),
+      draining: false,
+      drained: None,
+      metrics,
+    }
+  }
+}
+
+impl Actor for DownloadsActor {
+  type Message = DownloadEvent;
+
+  fn receive(&mut self, download_event: DownloadEvent) {
     match download_event {
       DownloadEvent::WorkspaceClosing(workspace_name) => {
         self
@@ -94,8 +135,19 @@ impl DownloadsActor {
             return false;
           });
       },
+      DownloadEvent::DeviceLeaving(workspace_name, device_name) => {
+        // Dropping the senders ends both sides of any transfer the device was part of
+        self.connections.retain(|key, _| {
+          key.workspace != workspace_name || (key.download_device != device_name && key.upload_device != device_name)
+        });
+      },
       DownloadEvent::CreateConnection(key, download_sender) => {
-        println!("Created connection for key {:?}", key);
+        if self.draining {
+          // Dropping the sender ends the download stream
+          tracing::warn!("Refusing download, the server is shutting down");
+          return;
+        }
+        tracing::info!("Created connection");
         let _ = self
           .connections
           .entry(key)
@@ -105,7 +157,7 @@ impl DownloadsActor {
         if let Some(connection) = self.connections.get_mut(&key) {
           connection.set_upload_sender(upload_sender);
 
-          println!("Sending download initiated for key {:?}", key);
+          tracing::info!("Sending download initiated");
           if let Err(err) = connection.download_sender.send(msg::DownloadResponse {
             r#type: Some(msg::download_response::Type::Initated(msg::DownloadInitated {
               number_of_chunks: request.number_of_chunks,
@@ -114,62 +166,97 @@ impl DownloadsActor {
               permissions: request.permissions.clone(),
             })),
           }) {
-            eprintln!("Error sending download initiated to download sender: {:?}", err);
+            tracing::warn!("Error sending download initiated to download sender: {:?}", err);
+            self.metrics.dropped("download");
           }
         } else {
-          eprintln!("No connection found for key {:?}", key);
+          tracing::warn!("No connection found");
         }
       },
       DownloadEvent::RequestFileChunk(key, offset) => {
         if let Some(connection) = self.connections.get_mut(&key) {
           if let Some(upload_sender) = &connection.upload_sender {
-            println!("Sending chunk request for key {:?}", key);
+            tracing::trace!(offset, "Sending chunk request");
             if let Err(err) = upload_sender.send(msg::UploadResponse {
               r#type: Some(msg::upload_response::Type::Request(msg::ChunkRequest { offset })),
             }) {
-              eprintln!("Error sending chunk request to upload sender: {:?}", err);
+              tracing::warn!("Error sending chunk request to upload sender: {:?}", err);
+              self.metrics.dropped("upload");
             }
           } else {
-            eprintln!("No upload sender found for key {:?}", key);
+            tracing::warn!("No upload sender found");
           }
         } else {
-          eprintln!("No connection found for key {:?}", key);
+          tracing::warn!("No connection found");
         }
       },
       DownloadEvent::SendFileChunk(key, chunk) => {
         if let Some(connection) = self.connections.get_mut(&key) {
-          println!("Sending chunk for key {:?}", key);
+          tracing::trace!(offset = chunk.offset, "Sending chunk");
+          let length = chunk.data.len() as u64;
           if let Err(err) = connection.download_sender.send(msg::DownloadResponse {
             r#type: Some(msg::download_response::Type::Chunk(msg::SharedFileChunk {
               offset: chunk.offset,
               data: chunk.data,
             })),
           }) {
-            eprintln!("Error sending chunk to download sender: {:?}", err);
+            tracing::warn!("Error sending chunk to download sender: {:?}", err);
+            self.metrics.dropped("download");
+          } else {
+            self.metrics.relayed_bytes.inc_by(length);
           }
         } else {
-          eprintln!("No connection found for key {:?}", key);
+          tracing::warn!("No connection found");
         }
       },
       DownloadEvent::DownloadComplete(key) => {
         if let Some(connection) = self.connections.remove(&key) {
           if let Some(upload_sender) = connection.upload_sender {
-            println!("Sending upload complete for key {:?}", key);
+            tracing::info!("Sending upload complete");
             if let Err(err) = upload_sender.send(msg::UploadResponse {
               r#type: Some(msg::upload_response::Type::Complete(msg::UploadComplete {})),
             }) {
-              eprintln!("Error sending upload complete to upload sender: {:?}", err);
+              tracing::warn!("Error sending upload complete to upload sender: {:?}", err);
+              self.metrics.dropped("upload");
             }
           } else {
-            eprintln!("No upload sender found for key {:?}", key);
+            tracing::warn!("No upload sender found");
           }
         } else {
-          eprintln!("No connection found for key {:?}", key);
+          tracing::warn!("No connection found");
         }
       },
-      DownloadEvent::ApplicationClosing => {
-        self.connections.clear();
+      DownloadEvent::Drain(reply) => {
+        tracing::info!("Draining {} transfers", self.connections.len());
+        self.draining = true;
+        self.drained = Some(reply);
       },
     }
+
+    self.metrics.active_downloads.set(self.connections.len() as i64);
+    if self.connections.is_empty() {
+      if let Some(drained) = self.drained.take() {
+        // Nobody is left waiting once the deadline has passed
+        let _ = drained.send(());
+      }
+    }
+  }
+
+  fn span(event: &DownloadEvent) -> tracing::Span {
+    match event {
+      DownloadEvent::CreateConnection(key, _)
+      | DownloadEvent::ConnectUploader(key, _, _)
+      | DownloadEvent::RequestFileChunk(key, _)
+      | DownloadEvent::SendFileChunk(key, _)
+      | DownloadEvent::DownloadComplete(key) => key.span(),
+      DownloadEvent::WorkspaceClosing(workspace_name) => workspace_span(workspace_name),
+      DownloadEvent::DeviceLeaving(workspace_name, device_name) => device_span(workspace_name, device_name),
+      DownloadEvent::Drain(_) => tracing::Span::none(),
+    }
+  }
+
+  fn stopped(&mut self) {
+    self.connections.clear();
+    self.metrics.active_downloads.set(0);
   }
 }
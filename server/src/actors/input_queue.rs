@@ -0,0 +1,394 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::time::Duration;
+use std::time::Instant;
+
+use tokio::sync::Notify;
+
+use crate::actors::device_map::StreamSender;
+use sinnergasm::protos as msg;
+
+const DEFAULT_CAPACITY: usize = 256;
+const DEFAULT_EVICTION: Duration = Duration::from_secs(2);
+
+// What happens to input that is not read fast enough, by a simulator or by the simulation actor. Past the capacity mouse moves are merged
+// into the last queued move, keys and buttons are still queued, and a simulator that stays over capacity
+// for longer than the eviction delay is evicted. Moves can also be merged before the capacity is reached.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OverflowPolicy {
+  capacity: usize,
+  evict_after: Duration,
+  coalesce: bool,
+}
+
+impl OverflowPolicy {
+  pub(crate) fn new(settings: &msg::WorkspaceSettings) -> Self {
+    Self {
+      capacity: match settings.input_queue_capacity {
+        0 => DEFAULT_CAPACITY,
+        capacity => capacity as usize,
+      },
+      evict_after: match settings.overflow_eviction_ms {
+        0 => DEFAULT_EVICTION,
+        eviction => Duration::from_millis(eviction as u64),
+      },
+      coalesce: settings.coalesce_mouse_moves,
+    }
+  }
+}
+
+impl Default for OverflowPolicy {
+  fn default() -> Self {
+    Self::new(&msg::WorkspaceSettings::default())
+  }
+}
+
+#[derive(Debug)]
+pub(crate) enum QueueError {
+  // The reading end was dropped
+  Closed,
+  // The queue has been over capacity for this long
+  Overflowed(Duration),
+}
+
+impl std::fmt::Display for QueueError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      QueueError::Closed => write!(f, "The simulation stream was closed"),
+      QueueError::Overflowed(duration) => write!(f, "The simulator has not kept up for {:?}", duration),
+    }
+  }
+}
+
+impl std::error::Error for QueueError {}
+
+#[derive(Debug, Default)]
+struct QueueState {
+  events: VecDeque<msg::SimulationEvent>,
+  senders: usize,
+  receiver_dropped: bool,
+  overflowing_since: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+  state: Mutex<QueueState>,
+  ready: Notify,
+}
+
+impl Shared {
+  // Nothing can panic while the lock is held, so a poisoned queue is still consistent
+  fn lock(&self) -> MutexGuard<'_, QueueState> {
+    self.state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+// The queue between the simulation actor and the stream of one simulator, or between a control stream and
+// the actor. The receiver ends once every sender is dropped, like an mpsc channel, but the queued input can
+// still be merged.
+pub(crate) fn input_queue() -> (InputSender, InputReceiver) {
+  let shared = Arc::new(Shared::default());
+  shared.lock().senders = 1;
+  (InputSender { shared: shared.clone() }, InputReceiver { shared })
+}
+
+#[derive(Debug)]
+pub(crate) struct InputSender {
+  shared: Arc<Shared>,
+}
+
+impl InputSender {
+  pub(crate) fn send(&self, event: msg::SimulationEvent, policy: &OverflowPolicy) -> Result<(), QueueError> {
+    let mut state = self.shared.lock();
+    if state.receiver_dropped {
+      return Err(QueueError::Closed);
+    }
+
+    let overflowing = state.events.len() >= policy.capacity;
+    if !overflowing {
+      state.overflowing_since = None;
+    } else {
+      let overflowing_since = *state.overflowing_since.get_or_insert_with(Instant::now);
+      if overflowing_since.elapsed() > policy.evict_after {
+        return Err(QueueError::Overflowed(overflowing_since.elapsed()));
+      }
+    }
+
+    if overflowing || policy.coalesce {
+      // Only a move that directly follows another is merged, so moves never jump over a key or button
+      if let (Some(delta), Some(input)) = (mouse_move(&event), event.input_event.as_ref()) {
+        if let Some(queued) = state.events.back_mut().and_then(mouse_move_input) {
+          if let Some(msg::user_input_event::Type::MouseMove(queued_move)) = queued.r#type.as_mut() {
+            queued_move.delta_x += delta.delta_x;
+            queued_move.delta_y += delta.delta_y;
+          }
+          // The merged move keeps the oldest timestamps, the sequence numbers it skips count as missed
+          queued.sequence = queued.sequence.max(input.sequence);
+          return Ok(());
+        }
+      }
+    }
+    if overflowing && event.input_event.is_none() {
+      // The queued input already shows the simulator that the stream is alive
+      return Ok(());
+    }
+
+    state.events.push_back(event);
+    drop(state);
+    self.shared.ready.notify_one();
+    Ok(())
+  }
+}
+
+impl Clone for InputSender {
+  fn clone(&self) -> Self {
+    self.shared.lock().senders += 1;
+    Self {
+      shared: self.shared.clone(),
+    }
+  }
+}
+
+impl Drop for InputSender {
+  fn drop(&mut self) {
+    let mut state = self.shared.lock();
+    state.senders -= 1;
+    if state.senders == 0 {
+      drop(state);
+      self.shared.ready.notify_one();
+    }
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct WeakInputSender {
+  shared: Weak<Shared>,
+}
+
+impl StreamSender for InputSender {
+  type Weak = WeakInputSender;
+
+  fn same_channel(&self, other: &Self) -> bool {
+    Arc::ptr_eq(&self.shared, &other.shared)
+  }
+
+  fn downgrade(&self) -> WeakInputSender {
+    WeakInputSender {
+      shared: Arc::downgrade(&self.shared),
+    }
+  }
+
+  fn upgrade(weak: &WeakInputSender) -> Option<Self> {
+    let shared = weak.shared.upgrade()?;
+    let mut state = shared.lock();
+    if state.senders == 0 {
+      return None;
+    }
+    state.senders += 1;
+    drop(state);
+    Some(Self { shared })
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct InputReceiver {
+  shared: Arc<Shared>,
+}
+
+impl InputReceiver {
+  // Waits for the next event, None once every sender is gone
+  pub(crate) async fn recv(&mut self) -> Option<msg::SimulationEvent> {
+    loop {
+      {
+        let mut state = self.shared.lock();
+        if let Some(event) = state.events.pop_front() {
+          return Some(event);
+        }
+        if state.senders == 0 {
+          return None;
+        }
+      }
+      self.shared.ready.notified().await;
+    }
+  }
+}
+
+impl Drop for InputReceiver {
+  fn drop(&mut self) {
+    let mut state = self.shared.lock();
+    state.receiver_dropped = true;
+    state.events.clear();
+  }
+}
+
+fn mouse_move(event: &msg::SimulationEvent) -> Option<&msg::MouseMoveEvent> {
+  match &event.input_event {
+    Some(msg::UserInputEvent {
+      r#type: Some(msg::user_input_event::Type::MouseMove(mouse_move)),
+      ..
+    }) => Some(mouse_move),
+    _ => None,
+  }
+}
+
+fn mouse_move_input(event: &mut msg::SimulationEvent) -> Option<&mut msg::UserInputEvent> {
+  match &mut event.input_event {
+    Some(
+      input @ msg::UserInputEvent {
+        r#type: Some(msg::user_input_event::Type::MouseMove(_)),
+        ..
+      },
+    ) => Some(input),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use msg::user_input_event::Type;
+
+  fn policy(capacity: usize, coalesce: bool) -> OverflowPolicy {
+    OverflowPolicy {
+      capacity,
+      evict_after: DEFAULT_EVICTION,
+      coalesce,
+    }
+  }
+
+  fn input(input: Type) -> msg::SimulationEvent {
+    msg::SimulationEvent {
+      input_event: Some(msg::UserInputEvent {
+        r#type: Some(input),
+        ..Default::default()
+      }),
+      ..Default::default()
+    }
+  }
+
+  fn heartbeat(sequence: u64) -> msg::SimulationEvent {
+    msg::SimulationEvent {
+      heartbeat: Some(msg::Heartbeat { sequence }),
+      ..Default::default()
+    }
+  }
+
+  fn mouse_move(delta_x: f64, delta_y: f64) -> Type {
+    Type::MouseMove(msg::MouseMoveEvent { delta_x, delta_y })
+  }
+
+  fn shift() -> msg::Key {
+    msg::Key {
+      key: Some(msg::key::Key::Code(msg::KeyCode::Shiftleft as i32)),
+    }
+  }
+
+  fn left_button() -> msg::MouseButton {
+    msg::MouseButton {
+      r#type: Some(msg::mouse_button::Type::Button(msg::MouseButtons::Left as i32)),
+    }
+  }
+
+  // Drops the sender and collects everything that was queued
+  async fn queued(sender: InputSender, mut receiver: InputReceiver) -> Vec<msg::SimulationEvent> {
+    drop(sender);
+    let mut queued = vec![];
+    while let Some(event) = receiver.recv().await {
+      queued.push(event);
+    }
+    queued
+  }
+
+  #[tokio::test]
+  async fn keys_and_buttons_are_queued_past_capacity() {
+    let (sender, receiver) = input_queue();
+    let policy = policy(1, false);
+    let events = vec![
+      input(Type::KeyPress(shift())),
+      input(Type::MousePress(left_button())),
+      input(Type::MouseRelease(left_button())),
+      input(Type::KeyRelease(shift())),
+    ];
+    for event in events.iter() {
+      sender.send(event.clone(), &policy).unwrap();
+    }
+
+    assert_eq!(queued(sender, receiver).await, events);
+  }
+
+  #[tokio::test]
+  async fn moves_are_only_merged_past_capacity_without_coalescing() {
+    let (sender, receiver) = input_queue();
+    let policy = policy(2, false);
+    sender.send(input(mouse_move(1.0, 0.0)), &policy).unwrap();
+    sender.send(input(mouse_move(1.0, 0.0)), &policy).unwrap();
+    sender.send(input(mouse_move(1.0, 1.0)), &policy).unwrap();
+    sender.send(input(Type::KeyPress(shift())), &policy).unwrap();
+    // Past capacity, but a move is not merged over a key
+    sender.send(input(mouse_move(0.0, 3.0)), &policy).unwrap();
+
+    assert_eq!(
+      queued(sender, receiver).await,
+      vec![
+        input(mouse_move(1.0, 0.0)),
+        input(mouse_move(2.0, 1.0)),
+        input(Type::KeyPress(shift())),
+        input(mouse_move(0.0, 3.0)),
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn heartbeats_are_dropped_past_capacity() {
+    let (sender, receiver) = input_queue();
+    let policy = policy(1, false);
+    sender.send(heartbeat(1), &policy).unwrap();
+    sender.send(heartbeat(2), &policy).unwrap();
+    sender.send(input(Type::KeyPress(shift())), &policy).unwrap();
+    sender.send(heartbeat(3), &policy).unwrap();
+
+    assert_eq!(
+      queued(sender, receiver).await,
+      vec![heartbeat(1), input(Type::KeyPress(shift()))]
+    );
+  }
+
+  #[tokio::test]
+  async fn overflows_once_over_capacity_for_longer_than_the_eviction_delay() {
+    let (sender, mut receiver) = input_queue();
+    let policy = OverflowPolicy {
+      capacity: 1,
+      evict_after: Duration::from_millis(10),
+      coalesce: false,
+    };
+    sender.send(input(Type::KeyPress(shift())), &policy).unwrap();
+    sender.send(input(Type::KeyRelease(shift())), &policy).unwrap();
+    std::thread::sleep(Duration::from_millis(20));
+
+    assert!(matches!(
+      sender.send(input(Type::KeyPress(shift())), &policy),
+      Err(QueueError::Overflowed(duration)) if duration > policy.evict_after
+    ));
+
+    // Catching up clears the overflow
+    receiver.recv().await.unwrap();
+    receiver.recv().await.unwrap();
+    sender.send(input(Type::KeyPress(shift())), &policy).unwrap();
+  }
+
+  #[tokio::test]
+  async fn recv_ends_once_the_last_sender_is_dropped() {
+    let (sender, mut receiver) = input_queue();
+    let other = sender.clone();
+    drop(sender);
+    other.send(heartbeat(1), &OverflowPolicy::default()).unwrap();
+
+    let waiting = tokio::spawn(async move {
+      let first = receiver.recv().await;
+      (first, receiver.recv().await)
+    });
+    drop(other);
+
+    assert_eq!(waiting.await.unwrap(), (Some(heartbeat(1)), None));
+  }
+}
@@ -1,4 +1,7 @@
 pub mod device_map;
 pub mod download_manager;
+pub mod input_queue;
+pub mod presence;
+pub mod pressed;
 pub mod simulate;
 pub mod workspace;
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::common as ids;
+use sinnergasm::protos as msg;
+
+// The roles each device is connected with, kept up to date from the presence events of the workspace
+#[derive(Debug, Default)]
+pub(crate) struct PresenceTable {
+  sessions: BTreeMap<ids::WorkspaceName, BTreeMap<(ids::DeviceName, msg::DeviceRole), SystemTime>>,
+}
+
+impl PresenceTable {
+  // Other events are ignored
+  pub(crate) fn record(&mut self, workspace_name: &ids::WorkspaceName, event: &msg::WorkspaceEvent) {
+    match &event.event_type {
+      Some(msg::workspace_event::EventType::DeviceConnected(msg::DeviceConnected { device, role })) => {
+        self
+          .sessions
+          .entry(workspace_name.clone())
+          .or_default()
+          .insert((device.clone(), role_of(*role)), SystemTime::now());
+      }
+      Some(msg::workspace_event::EventType::DeviceDisconnected(msg::DeviceDisconnected { device, role })) => {
+        if let Some(sessions) = self.sessions.get_mut(workspace_name) {
+          sessions.remove(&(device.clone(), role_of(*role)));
+          if sessions.is_empty() {
+            self.sessions.remove(workspace_name);
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+
+  pub(crate) fn sessions(&self, workspace_name: &ids::WorkspaceName) -> Vec<msg::DeviceSession> {
+    self
+      .sessions
+      .get(workspace_name)
+      .into_iter()
+      .flatten()
+      .map(|((device, role), connected_at)| msg::DeviceSession {
+        device: device.clone(),
+        role: (*role).into(),
+        connected_at: connected_at
+          .duration_since(UNIX_EPOCH)
+          .map(|elapsed| elapsed.as_millis() as u64)
+          .unwrap_or_default(),
+      })
+      .collect()
+  }
+
+  // How many devices are connected with the role, across every workspace
+  pub(crate) fn connected(&self, role: msg::DeviceRole) -> usize {
+    self
+      .sessions
+      .values()
+      .flat_map(|sessions| sessions.keys())
+      .filter(|(_, session_role)| *session_role == role)
+      .count()
+  }
+
+  pub(crate) fn remove_workspace(&mut self, workspace_name: &ids::WorkspaceName) {
+    self.sessions.remove(workspace_name);
+  }
+
+  pub(crate) fn clear(&mut self) {
+    self.sessions.clear();
+  }
+}
+
+fn role_of(role: i32) -> msg::DeviceRole {
+  msg::DeviceRole::from_i32(role).unwrap_or(msg::DeviceRole::Unknown)
+}
@@ -0,0 +1,83 @@
+use sinnergasm::protos as msg;
+
+const MODIFIERS: [msg::KeyCode; 9] = [
+  msg::KeyCode::Alt,
+  msg::KeyCode::Altgr,
+  msg::KeyCode::Controlleft,
+  msg::KeyCode::Controlright,
+  msg::KeyCode::Metaleft,
+  msg::KeyCode::Metaright,
+  msg::KeyCode::Shiftleft,
+  msg::KeyCode::Shiftright,
+  msg::KeyCode::Function,
+];
+
+// The keys and buttons held down on the target of a workspace, in the order they were pressed
+#[derive(Debug, Default)]
+pub(crate) struct PressedInput {
+  keys: Vec<msg::Key>,
+  buttons: Vec<msg::MouseButton>,
+}
+
+impl PressedInput {
+  pub(crate) fn track(&mut self, event: &msg::UserInputEvent) {
+    match &event.r#type {
+      Some(msg::user_input_event::Type::KeyPress(key)) if !self.keys.contains(key) => {
+        self.keys.push(key.clone());
+      }
+      Some(msg::user_input_event::Type::KeyRelease(key)) => self.keys.retain(|pressed| pressed != key),
+      Some(msg::user_input_event::Type::MousePress(button)) if !self.buttons.contains(button) => {
+        self.buttons.push(button.clone());
+      }
+      Some(msg::user_input_event::Type::MouseRelease(button)) => {
+        self.buttons.retain(|pressed| pressed != button);
+      }
+      _ => {}
+    }
+  }
+
+  // Releases everything that is held, most recently pressed first
+  pub(crate) fn releases(&self) -> Vec<msg::SimulationEvent> {
+    let buttons = self
+      .buttons
+      .iter()
+      .rev()
+      .map(|button| msg::user_input_event::Type::MouseRelease(button.clone()));
+    let keys = self
+      .keys
+      .iter()
+      .rev()
+      .map(|key| msg::user_input_event::Type::KeyRelease(key.clone()));
+    buttons.chain(keys).map(simulation_event).collect()
+  }
+
+  // Only the modifiers carry over to a new target, the remaining keys and buttons are forgotten
+  pub(crate) fn carry_modifiers(self) -> (PressedInput, Vec<msg::SimulationEvent>) {
+    let keys = self.keys.into_iter().filter(is_modifier).collect::<Vec<_>>();
+    let presses = keys
+      .iter()
+      .map(|key| simulation_event(msg::user_input_event::Type::KeyPress(key.clone())))
+      .collect();
+    (PressedInput { keys, buttons: vec![] }, presses)
+  }
+}
+
+fn is_modifier(key: &msg::Key) -> bool {
+  match key.key {
+    Some(msg::key::Key::Code(code)) => MODIFIERS.iter().any(|modifier| *modifier as i32 == code),
+    _ => false,
+  }
+}
+
+// Not stamped, nothing captured this input
+fn simulation_event(event: msg::user_input_event::Type) -> msg::SimulationEvent {
+  msg::SimulationEvent {
+    input_event: Some(msg::UserInputEvent {
+      r#type: Some(event),
+      sequence: 0,
+      captured_at_us: 0,
+    }),
+    heartbeat: None,
+    relayed_at_us: 0,
+  }
+}
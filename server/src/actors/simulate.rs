@@ -1,83 +1,424 @@
+use crate::actor::device_span;
+use crate::actor::workspace_span;
+use crate::actor::Actor;
+use crate::actor::ActorHandle;
 use crate::actors::device_map::DeviceMap;
+use crate::actors::input_queue::InputSender;
+use crate::actors::input_queue::OverflowPolicy;
+use crate::actors::pressed::PressedInput;
+use crate::actors::workspace::presence_event;
+use crate::actors::workspace::SubscriptionEvent;
+use crate::metrics::Metrics;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::sync::Arc;
+use tokio::sync::oneshot;
 
 use crate::common as ids;
 use sinnergasm::protos as msg;
 
 pub(crate) enum SimulationEvent {
-  AddSimulator(
-    ids::WorkspaceName,
-    ids::DeviceName,
-    tokio::sync::mpsc::UnboundedSender<msg::SimulationEvent>,
-  ),
+  AddSimulator(ids::WorkspaceName, ids::DeviceName, InputSender),
   RemoveSimulator(ids::WorkspaceName, ids::DeviceName),
+  // The client dropped the simulation stream
+  SimulatorClosed(ids::WorkspaceName, ids::DeviceName, InputSender),
+  // Replies whether the device has an open simulation stream
+  IsSimulating(ids::WorkspaceName, ids::DeviceName, oneshot::Sender<bool>),
   TargetEvent(ids::WorkspaceName, ids::DeviceName),
-  SimulationEvent(ids::WorkspaceName, msg::SimulationEvent),
+  ApplySettings(ids::WorkspaceName, msg::WorkspaceSettings),
+  // Replied to once the input is queued for the target, so the control stream only relays the next after
+  SimulationEvent(ids::WorkspaceName, msg::SimulationEvent, oneshot::Sender<()>),
   WorkspaceClosing(ids::WorkspaceName),
-  ApplicationClosing,
+  DeviceHeartbeat(ids::WorkspaceName, ids::DeviceName),
+  // Evicts simulators that have been silent for longer than the timeout and sends everyone else a heartbeat
+  CheckLiveness(std::time::Duration),
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(crate) struct SimulationActor {
-  listeners: BTreeMap<ids::WorkspaceName, DeviceMap<msg::SimulationEvent>>,
+  listeners: BTreeMap<ids::WorkspaceName, DeviceMap<InputSender>>,
+  // Used to announce simulators to the subscribers of the workspace
+  workspace_sender: ActorHandle<SubscriptionEvent>,
+  heartbeat_sequence: u64,
+  // What is held down on the current target of each workspace
+  pressed: BTreeMap<ids::WorkspaceName, PressedInput>,
+  settings: BTreeMap<ids::WorkspaceName, msg::WorkspaceSettings>,
+  metrics: Arc<Metrics>,
 }
 
 impl SimulationActor {
-  pub(crate) fn receive(&mut self, event: SimulationEvent) {
+  pub(crate) fn new(
+    workspace_sender: ActorHandle<SubscriptionEvent>,
+    settings: BTreeMap<ids::WorkspaceName, msg::WorkspaceSettings>,
+    metrics: Arc<Metrics>,
+  ) -> Self {
+    Self {
+      listeners: BTreeMap::new(),
+      workspace_sender,
+      heartbeat_sequence: 0,
+      pressed: BTreeMap::new(),
+      settings,
+      metrics,
+    }
+  }
+
+  fn announce(&self, workspace_name: &ids::WorkspaceName, device_name: &ids::DeviceName, connected: bool) {
+    if let Err(err) = self.workspace_sender.send(SubscriptionEvent::WorkspaceEvent(
+      workspace_name.clone(),
+      presence_event(device_name, msg::DeviceRole::Simulator, connected),
+    )) {
+      tracing::error!(device = %device_name, "Failed to announce simulator: {:?}", err);
+    }
+  }
+
+  // Queues the input for the target of the workspace
+  fn relay(&mut self, workspace_name: ids::WorkspaceName, event: msg::SimulationEvent) {
+    let policy = self.overflow_policy(&workspace_name);
+    if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
+      if let Some((target, sender)) = device_map.target.as_ref() {
+        if let Err(err) = sender.send(event.clone(), &policy) {
+          tracing::warn!(workspace = %workspace_name, device = %target, "Failed to send input: {}", err);
+          self.metrics.dropped("simulator");
+          // Dropping the sender ends the stream of a simulator that could not keep up
+          let target = target.clone();
+          device_map.remove(&target);
+          self.pressed.remove(&workspace_name);
+          self.announce(&workspace_name, &target, false);
+        } else if let Some(input_event) = event.input_event.as_ref() {
+          self.pressed.entry(workspace_name).or_default().track(input_event);
+        }
+      } else {
+        tracing::trace!(workspace = %workspace_name, "No target for the input");
+      }
+    } else {
+      tracing::trace!(workspace = %workspace_name, "No simulation listeners for the input");
+    }
+  }
+
+  fn overflow_policy(&self, workspace_name: &ids::WorkspaceName) -> OverflowPolicy {
+    self
+      .settings
+      .get(workspace_name)
+      .map(OverflowPolicy::new)
+      .unwrap_or_default()
+  }
+}
+
+impl Actor for SimulationActor {
+  type Message = SimulationEvent;
+
+  fn receive(&mut self, event: SimulationEvent) {
     match event {
       SimulationEvent::AddSimulator(workspace_name, device_name, sender) => {
-        let device_map = self.listeners.entry(workspace_name).or_insert_with(DeviceMap::default);
+        let device_map = self.listeners.entry(workspace_name.clone()).or_default();
         // device_map.target = Some((device_name.clone(), sender.clone()));
-        device_map.devices.insert(device_name, sender);
-        println!("Added simulator for workspace");
+        device_map.insert(device_name.clone(), sender);
+        tracing::info!("Added simulator");
+        self.announce(&workspace_name, &device_name, true);
       }
       SimulationEvent::RemoveSimulator(workspace_name, device_name) => {
         if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
-          if let Some((target, _)) = device_map.target.as_ref() {
-            if target == &device_name {
-              device_map.target = None;
-            }
+          let removed = device_map.devices.contains_key(&device_name);
+          device_map.remove(&device_name);
+          if device_map.devices.is_empty() {
+            self.listeners.remove(&workspace_name);
+          }
+          if removed {
+            self.announce(&workspace_name, &device_name, false);
           }
-          device_map.devices.remove(&device_name);
+        }
+      }
+      SimulationEvent::SimulatorClosed(workspace_name, device_name, sender) => {
+        if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
+          let removed = device_map.remove_channel(&device_name, &sender);
           if device_map.devices.is_empty() {
             self.listeners.remove(&workspace_name);
           }
+          if removed {
+            tracing::info!("Simulator was closed");
+            self.announce(&workspace_name, &device_name, false);
+          }
+        }
+      }
+      SimulationEvent::IsSimulating(workspace_name, device_name, reply) => {
+        let simulating = self
+          .listeners
+          .get(&workspace_name)
+          .is_some_and(|device_map| device_map.devices.contains_key(&device_name));
+        if reply.send(simulating).is_err() {
+          tracing::debug!("Simulation query was cancelled before it was answered");
         }
       }
       SimulationEvent::TargetEvent(workspace_name, device_name) => {
+        let policy = self.overflow_policy(&workspace_name);
         if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
+          if matches!(&device_map.target, Some((target, _)) if target == &device_name) {
+            return;
+          }
+          let pressed = self.pressed.remove(&workspace_name).unwrap_or_default();
+          // Without a target nothing was relayed, whatever is left over belonged to a simulator that is gone
+          let pressed = match device_map.target.as_ref() {
+            Some((target, sender)) => {
+              // Anything still held down would otherwise stay stuck on the old target
+              for release in pressed.releases() {
+                if let Err(err) = sender.send(release, &policy) {
+                  tracing::warn!(old_target = %target, "Failed to release input: {:?}", err);
+                  self.metrics.dropped("simulator");
+                  break;
+                }
+              }
+              pressed
+            }
+            None => PressedInput::default(),
+          };
+
           if let Some(sender) = device_map.devices.get(&device_name) {
             device_map.target = Some((device_name, sender.clone()));
           } else {
-            println!(
-              "Target: new simulation target not found: {} in {}. Maybe it is the controller?",
-              device_name, workspace_name
-            );
+            // A controller targetting itself uses its own input, nothing is simulated
+            device_map.target = None;
           }
-        } else {
-          println!("Target: No simulation listeners for workspace: {}", workspace_name);
-        }
-      }
-      SimulationEvent::SimulationEvent(workspace_name, event) => {
-        if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
-          if let Some((_, sender)) = device_map.target.as_ref() {
-            if let Err(err) = sender.send(event.clone()) {
-              println!("Failed to send event to listener: {:?}", err);
+
+          let repress = self
+            .settings
+            .get(&workspace_name)
+            .is_some_and(|settings| settings.repress_modifiers);
+          if let (true, Some((_, sender))) = (repress, device_map.target.as_ref()) {
+            let (pressed, presses) = pressed.carry_modifiers();
+            for press in presses {
+              if let Err(err) = sender.send(press, &policy) {
+                tracing::warn!("Failed to press modifiers on the new target: {:?}", err);
+                self.metrics.dropped("simulator");
+                break;
+              }
             }
-          } else {
-            println!("No target for workspace");
+            self.pressed.insert(workspace_name, pressed);
           }
         } else {
-          println!("No simulation listeners for workspace: {}", workspace_name);
+          tracing::info!("No simulation listeners to target");
+        }
+      }
+      SimulationEvent::ApplySettings(workspace_name, settings) => {
+        self.settings.insert(workspace_name, settings);
+      }
+      SimulationEvent::SimulationEvent(workspace_name, event, relayed) => {
+        self.relay(workspace_name, event);
+        if relayed.send(()).is_err() {
+          tracing::debug!("The control stream ended before its input was relayed");
         }
       }
       SimulationEvent::WorkspaceClosing(workspace_name) => {
         self.listeners.remove(&workspace_name);
+        self.pressed.remove(&workspace_name);
+      }
+      SimulationEvent::DeviceHeartbeat(workspace_name, device_name) => {
+        if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
+          device_map.seen(&device_name);
+        }
+      }
+      SimulationEvent::CheckLiveness(timeout) => {
+        self.heartbeat_sequence += 1;
+        let heartbeat = msg::SimulationEvent {
+          input_event: None,
+          heartbeat: Some(msg::Heartbeat {
+            sequence: self.heartbeat_sequence,
+          }),
+          relayed_at_us: 0,
+        };
+        let mut evicted = vec![];
+        let settings = &self.settings;
+        let metrics = &self.metrics;
+        for (workspace_name, device_map) in self.listeners.iter_mut() {
+          let policy = settings
+            .get(workspace_name)
+            .map(OverflowPolicy::new)
+            .unwrap_or_default();
+          for device in device_map.expired(timeout) {
+            tracing::info!(workspace = %workspace_name, device = %device, "Evicting silent simulator");
+            device_map.remove(&device);
+            evicted.push((workspace_name.clone(), device));
+          }
+          let dropped = device_map
+            .devices
+            .iter()
+            .filter(|(_, sender)| sender.send(heartbeat.clone(), &policy).is_err())
+            .map(|(device, _)| device.clone())
+            .collect::<Vec<_>>();
+          for device in dropped {
+            metrics.dropped("simulator");
+            device_map.remove(&device);
+            evicted.push((workspace_name.clone(), device));
+          }
+        }
+        self.listeners.retain(|_, device_map| !device_map.is_empty());
+        for (workspace_name, device) in evicted {
+          self.announce(&workspace_name, &device, false);
+        }
       }
-      SimulationEvent::ApplicationClosing => {
-        self.listeners.clear();
+    }
+  }
+
+  fn span(event: &SimulationEvent) -> tracing::Span {
+    match event {
+      SimulationEvent::AddSimulator(workspace_name, device_name, _)
+      | SimulationEvent::RemoveSimulator(workspace_name, device_name)
+      | SimulationEvent::SimulatorClosed(workspace_name, device_name, _)
+      | SimulationEvent::IsSimulating(workspace_name, device_name, _)
+      | SimulationEvent::TargetEvent(workspace_name, device_name)
+      | SimulationEvent::DeviceHeartbeat(workspace_name, device_name) => device_span(workspace_name, device_name),
+      // Input is relayed too often for a span of its own
+      SimulationEvent::SimulationEvent(_, _, _) | SimulationEvent::CheckLiveness(_) => tracing::Span::none(),
+      SimulationEvent::ApplySettings(workspace_name, _) | SimulationEvent::WorkspaceClosing(workspace_name) => {
+        workspace_span(workspace_name)
       }
     }
   }
+
+  fn stopped(&mut self) {
+    self.listeners.clear();
+    self.pressed.clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::actor::ActorSystem;
+  use crate::actors::input_queue::input_queue;
+  use crate::actors::input_queue::InputReceiver;
+  use crate::actors::workspace::WorkspaceActor;
+  use msg::user_input_event::Type;
+
+  const WORKSPACE: &str = "workspace";
+  const SIMULATOR: &str = "simulator";
+
+  // A targetted simulator that only reads its stream when the test does
+  fn lagging_simulator(coalesce_mouse_moves: bool) -> (SimulationActor, InputReceiver) {
+    let metrics = Arc::new(Metrics::new().unwrap());
+    let mut actors = ActorSystem::new(metrics.clone());
+    let workspace_metrics = metrics.clone();
+    let workspace_sender = actors.spawn("workspace", move || WorkspaceActor::new(workspace_metrics.clone()));
+    let mut actor = SimulationActor::new(workspace_sender, BTreeMap::new(), metrics);
+    let (sender, receiver) = input_queue();
+    actor.receive(SimulationEvent::ApplySettings(
+      WORKSPACE.into(),
+      msg::WorkspaceSettings {
+        coalesce_mouse_moves,
+        ..Default::default()
+      },
+    ));
+    actor.receive(SimulationEvent::AddSimulator(
+      WORKSPACE.into(),
+      SIMULATOR.into(),
+      sender,
+    ));
+    actor.receive(SimulationEvent::TargetEvent(WORKSPACE.into(), SIMULATOR.into()));
+    (actor, receiver)
+  }
+
+  fn input(actor: &mut SimulationActor, input: Type) {
+    actor.receive(SimulationEvent::SimulationEvent(
+      WORKSPACE.into(),
+      msg::SimulationEvent {
+        input_event: Some(msg::UserInputEvent {
+          r#type: Some(input),
+          ..Default::default()
+        }),
+        heartbeat: None,
+        relayed_at_us: 0,
+      },
+      oneshot::channel().0,
+    ));
+  }
+
+  fn mouse_move(delta_x: f64, delta_y: f64) -> Type {
+    Type::MouseMove(msg::MouseMoveEvent { delta_x, delta_y })
+  }
+
+  fn shift() -> msg::Key {
+    msg::Key {
+      key: Some(msg::key::Key::Code(msg::KeyCode::Shiftleft as i32)),
+    }
+  }
+
+  fn left_button() -> msg::MouseButton {
+    msg::MouseButton {
+      r#type: Some(msg::mouse_button::Type::Button(msg::MouseButtons::Left as i32)),
+    }
+  }
+
+  // Ends the stream and collects the input the simulator would have received
+  async fn delivered(mut actor: SimulationActor, mut receiver: InputReceiver) -> Vec<Type> {
+    actor.receive(SimulationEvent::RemoveSimulator(WORKSPACE.into(), SIMULATOR.into()));
+    let mut delivered = vec![];
+    while let Some(event) = receiver.recv().await {
+      if let Some(msg::UserInputEvent {
+        r#type: Some(input), ..
+      }) = event.input_event
+      {
+        delivered.push(input);
+      }
+    }
+    delivered
+  }
+
+  #[tokio::test]
+  async fn pending_mouse_moves_are_summed() {
+    let (mut actor, receiver) = lagging_simulator(true);
+    input(&mut actor, mouse_move(1.0, 2.0));
+    input(&mut actor, mouse_move(3.0, 4.0));
+    input(&mut actor, mouse_move(-1.0, 0.5));
+
+    assert_eq!(delivered(actor, receiver).await, vec![mouse_move(3.0, 6.5)]);
+  }
+
+  #[tokio::test]
+  async fn moves_keep_their_order_with_keys_and_buttons() {
+    let (mut actor, receiver) = lagging_simulator(true);
+    input(&mut actor, mouse_move(1.0, 1.0));
+    input(&mut actor, mouse_move(1.0, 1.0));
+    input(&mut actor, Type::KeyPress(shift()));
+    input(&mut actor, mouse_move(2.0, 0.0));
+    input(&mut actor, Type::MousePress(left_button()));
+    input(&mut actor, Type::MouseRelease(left_button()));
+    input(&mut actor, mouse_move(0.0, 5.0));
+    input(&mut actor, mouse_move(0.0, 5.0));
+    input(&mut actor, Type::KeyRelease(shift()));
+
+    assert_eq!(
+      delivered(actor, receiver).await,
+      vec![
+        mouse_move(2.0, 2.0),
+        Type::KeyPress(shift()),
+        mouse_move(2.0, 0.0),
+        Type::MousePress(left_button()),
+        Type::MouseRelease(left_button()),
+        mouse_move(0.0, 10.0),
+        Type::KeyRelease(shift()),
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn moves_already_picked_up_are_not_merged_into() {
+    let (mut actor, mut receiver) = lagging_simulator(true);
+    input(&mut actor, mouse_move(1.0, 1.0));
+    let picked_up = receiver.recv().await.and_then(|event| event.input_event);
+    input(&mut actor, mouse_move(2.0, 2.0));
+
+    assert_eq!(picked_up.and_then(|event| event.r#type), Some(mouse_move(1.0, 1.0)));
+    assert_eq!(delivered(actor, receiver).await, vec![mouse_move(2.0, 2.0)]);
+  }
+
+  #[tokio::test]
+  async fn moves_are_relayed_one_by_one_without_the_setting() {
+    let (mut actor, receiver) = lagging_simulator(false);
+    input(&mut actor, mouse_move(1.0, 2.0));
+    input(&mut actor, mouse_move(3.0, 4.0));
+
+    assert_eq!(
+      delivered(actor, receiver).await,
+      vec![mouse_move(1.0, 2.0), mouse_move(3.0, 4.0)]
+    );
+  }
 }
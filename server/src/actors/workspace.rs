@@ -1,12 +1,49 @@
-use core::panic;
 use std::collections::BTreeMap;
+use std::sync::Arc;
 
+use crate::actor::device_span;
+use crate::actor::workspace_span;
+use crate::actor::Actor;
 use crate::actors::device_map::DeviceMap;
+use crate::actors::presence::PresenceTable;
 use crate::common as ids;
+use crate::metrics::Metrics;
 use sinnergasm::protos as msg;
+use sinnergasm::telemetry;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
 
 const MAXIMUM_BUFFER_SIZE: u64 = 16384;
 
+#[derive(Debug)]
+pub(crate) enum TargetError {
+  NotSubscribed(ids::WorkspaceName, ids::DeviceName),
+  NotSimulating(ids::WorkspaceName, ids::DeviceName),
+}
+
+impl std::fmt::Display for TargetError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      TargetError::NotSubscribed(workspace, device) => {
+        write!(f, "Device {} is not subscribed to workspace {}", device, workspace)
+      }
+      TargetError::NotSimulating(workspace, device) => write!(
+        f,
+        "Device {} in workspace {} is not simulating and is not a controller",
+        device, workspace
+      ),
+    }
+  }
+}
+
+impl std::error::Error for TargetError {}
+
+impl From<TargetError> for tonic::Status {
+  fn from(err: TargetError) -> Self {
+    tonic::Status::failed_precondition(err.to_string())
+  }
+}
+
 pub(crate) enum SubscriptionEvent {
   Subscribe(
     ids::WorkspaceName,
@@ -14,40 +51,125 @@ pub(crate) enum SubscriptionEvent {
     tokio::sync::mpsc::UnboundedSender<msg::WorkspaceEvent>,
   ),
   Unsubscribe(ids::WorkspaceName, ids::DeviceName),
+  // The client dropped the subscription stream
+  SubscriptionClosed(
+    ids::WorkspaceName,
+    ids::DeviceName,
+    tokio::sync::mpsc::UnboundedSender<msg::WorkspaceEvent>,
+  ),
   WorkspaceEvent(ids::WorkspaceName, msg::WorkspaceEvent),
   WorskpaceClosing(ids::WorkspaceName),
-  ApplicationClosing,
-  TargetEvent(ids::WorkspaceName, ids::DeviceName, Option<String>),
+  // Replies with the previous target
+  TargetEvent(
+    ids::WorkspaceName,
+    ids::DeviceName,
+    Option<String>,
+    oneshot::Sender<Result<Option<ids::DeviceName>, TargetError>>,
+  ),
   DownloadRequested(ids::WorkspaceName, msg::InitiateDownload),
+  DeviceHeartbeat(ids::WorkspaceName, ids::DeviceName),
+  // Evicts devices that have been silent for longer than the timeout and sends everyone else a heartbeat
+  CheckLiveness(std::time::Duration),
+  Status(ids::WorkspaceName, oneshot::Sender<LiveStatus>),
+  // Warns every subscriber before the streams are ended, transfers get the grace period to finish
+  ServerShuttingDown(std::time::Duration),
+}
+
+// The state of a workspace that only exists while devices are connected
+#[derive(Debug)]
+pub(crate) struct LiveStatus {
+  pub(crate) target: Option<ids::DeviceName>,
+  pub(crate) sessions: Vec<msg::DeviceSession>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(crate) struct WorkspaceActor {
-  listeners: BTreeMap<ids::WorkspaceName, DeviceMap<msg::WorkspaceEvent>>,
+  listeners: BTreeMap<ids::WorkspaceName, DeviceMap<UnboundedSender<msg::WorkspaceEvent>>>,
+  // Every role a device connects with is announced through this actor, so it keeps the record
+  presence: PresenceTable,
+  heartbeat_sequence: u64,
+  metrics: Arc<Metrics>,
 }
 
 impl WorkspaceActor {
-  pub(crate) fn receive(&mut self, event: SubscriptionEvent) {
+  pub(crate) fn new(metrics: Arc<Metrics>) -> Self {
+    Self {
+      listeners: BTreeMap::new(),
+      presence: PresenceTable::default(),
+      heartbeat_sequence: 0,
+      metrics,
+    }
+  }
+
+  fn update_connected_devices(&self) {
+    for role in [
+      msg::DeviceRole::Subscriber,
+      msg::DeviceRole::Simulator,
+      msg::DeviceRole::Controller,
+    ] {
+      self
+        .metrics
+        .connected_devices
+        .with_label_values(&[&role.as_str_name().to_lowercase()])
+        .set(self.presence.connected(role) as i64);
+    }
+  }
+}
+
+impl Actor for WorkspaceActor {
+  type Message = SubscriptionEvent;
+
+  fn receive(&mut self, event: SubscriptionEvent) {
     match event {
       SubscriptionEvent::Subscribe(workspace_name, device_name, sender) => {
-        self
-          .listeners
-          .entry(workspace_name)
-          .or_insert_with(DeviceMap::default)
-          .devices
-          .insert(device_name, sender);
+        let device_map = self.listeners.entry(workspace_name.clone()).or_default();
+        device_map.insert(device_name.clone(), sender);
+        broadcast(
+          device_map,
+          &mut self.presence,
+          &self.metrics,
+          &workspace_name,
+          presence_event(&device_name, msg::DeviceRole::Subscriber, true),
+        );
       }
       SubscriptionEvent::Unsubscribe(workspace_id, device_id) => {
         if let Some(device_map) = self.listeners.get_mut(&workspace_id) {
-          device_map.remove(&device_id);
+          if device_map.devices.contains_key(&device_id) {
+            device_map.remove(&device_id);
+            broadcast(
+              device_map,
+              &mut self.presence,
+              &self.metrics,
+              &workspace_id,
+              presence_event(&device_id, msg::DeviceRole::Subscriber, false),
+            );
+          }
           // Do this in the workspace actor...
           if device_map.is_empty() {
             self.listeners.remove(&workspace_id);
           }
         }
       }
+      SubscriptionEvent::SubscriptionClosed(workspace_name, device_name, sender) => {
+        if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
+          if device_map.remove_channel(&device_name, &sender) {
+            tracing::info!("Subscription was closed");
+            broadcast(
+              device_map,
+              &mut self.presence,
+              &self.metrics,
+              &workspace_name,
+              presence_event(&device_name, msg::DeviceRole::Subscriber, false),
+            );
+          }
+          if device_map.is_empty() {
+            self.listeners.remove(&workspace_name);
+          }
+        }
+      }
       SubscriptionEvent::WorskpaceClosing(workspace_name) => {
         self.listeners.remove(&workspace_name);
+        self.presence.remove_workspace(&workspace_name);
       }
       SubscriptionEvent::WorkspaceEvent(workspace_name, event) => {
         if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
@@ -57,25 +179,20 @@ impl WorkspaceActor {
             if let Some(sender) = device_map.devices.get(&device) {
               device_map.target = Some((device.clone(), sender.clone()));
             } else {
-              println!("Targetted workspace device is not present {:?}", device);
+              tracing::warn!(target_device = %device, "Targetted workspace device is not present");
             }
-            panic!("Target update event should not be sent to workspace actor");
           }
-          device_map.devices.retain(|_, listener| {
-            if let Err(err) = listener.send(event.clone()) {
-              println!("Failed to send event to listener: {:?}", err);
-              return false;
-            }
-            return true;
-          });
+          broadcast(device_map, &mut self.presence, &self.metrics, &workspace_name, event);
         } else {
-          println!("No workspace listeners for workspace: {:?}", workspace_name);
+          tracing::debug!("No workspace listeners");
+          // Simulators and controllers can be connected without anyone subscribed
+          self.presence.record(&workspace_name, &event);
         }
       }
       SubscriptionEvent::DownloadRequested(workspace_name, initiate_request) => {
         if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
           if let Some(uploader) = device_map.devices.get(&initiate_request.upload_device) {
-            println!("Sending download request to uploader");
+            tracing::info!(uploader = %initiate_request.upload_device, "Sending download request to uploader");
             if let Err(err) = uploader.send(msg::WorkspaceEvent {
               event_type: Some(msg::workspace_event::EventType::DownloadRequest(msg::UploadRequested {
                 download_device: initiate_request.download_device.clone(),
@@ -84,83 +201,211 @@ impl WorkspaceActor {
                   .buffer_size
                   .map(|x| std::cmp::max(x, MAXIMUM_BUFFER_SIZE)),
               })),
+              // The uploader continues the trace of the download
+              trace_context: telemetry::current_context(),
             }) {
-              println!("Failed to send download request to uploader: {:?}", err);
+              tracing::warn!("Failed to send download request to uploader: {:?}", err);
+              self.metrics.dropped("subscriber");
             }
           } else {
-            println!("No uploader device found for workspace: {:?}", workspace_name);
+            tracing::warn!(uploader = %initiate_request.upload_device, "No uploader device found");
           }
         } else {
-          println!("No workspace listeners for workspace: {:?}", workspace_name);
+          tracing::warn!("No workspace listeners");
         }
       }
-      SubscriptionEvent::ApplicationClosing => {
-        self.listeners.clear();
+      SubscriptionEvent::DeviceHeartbeat(workspace_name, device_name) => {
+        if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
+          device_map.seen(&device_name);
+        }
+      }
+      SubscriptionEvent::CheckLiveness(timeout) => {
+        self.heartbeat_sequence += 1;
+        let heartbeat = msg::WorkspaceEvent {
+          event_type: Some(msg::workspace_event::EventType::Heartbeat(msg::Heartbeat {
+            sequence: self.heartbeat_sequence,
+          })),
+          trace_context: Default::default(),
+        };
+        for (workspace_name, device_map) in self.listeners.iter_mut() {
+          for device in device_map.expired(timeout) {
+            tracing::info!(workspace = %workspace_name, device = %device, "Evicting silent subscriber");
+            device_map.remove(&device);
+            broadcast(
+              device_map,
+              &mut self.presence,
+              &self.metrics,
+              workspace_name,
+              presence_event(&device, msg::DeviceRole::Subscriber, false),
+            );
+          }
+          broadcast(
+            device_map,
+            &mut self.presence,
+            &self.metrics,
+            workspace_name,
+            heartbeat.clone(),
+          );
+        }
+        self.listeners.retain(|_, device_map| !device_map.is_empty());
+      }
+      SubscriptionEvent::TargetEvent(workspace_name, device_name, clipboard, reply) => {
+        let result = self.handle_target_event(workspace_name, device_name, clipboard);
+        if reply.send(result).is_err() {
+          tracing::debug!("Target request was cancelled before it was answered");
+        }
       }
-      SubscriptionEvent::TargetEvent(workspace_name, device_name, clipboard) => {
-        self.handle_target_event(workspace_name, device_name, clipboard);
+      SubscriptionEvent::Status(workspace_name, reply) => {
+        let status = LiveStatus {
+          target: self
+            .listeners
+            .get(&workspace_name)
+            .and_then(|device_map| device_map.target.as_ref())
+            .map(|(target, _)| target.clone()),
+          sessions: self.presence.sessions(&workspace_name),
+        };
+        if reply.send(status).is_err() {
+          tracing::debug!("Status request was cancelled before it was answered");
+        }
+      }
+      SubscriptionEvent::ServerShuttingDown(grace_period) => {
+        let shutting_down = msg::WorkspaceEvent {
+          event_type: Some(msg::workspace_event::EventType::ServerShuttingDown(
+            msg::ServerShuttingDown {
+              grace_period_ms: grace_period.as_millis() as u64,
+            },
+          )),
+          trace_context: Default::default(),
+        };
+        for (workspace_name, device_map) in self.listeners.iter_mut() {
+          broadcast(
+            device_map,
+            &mut self.presence,
+            &self.metrics,
+            workspace_name,
+            shutting_down.clone(),
+          );
+        }
       }
     }
+    self.update_connected_devices();
   }
 
-  fn handle_target_event(&mut self, workspace_name: String, device_name: String, clipboard: Option<String>) {
-    // TODO: clean this method up, DRY
-    if let Some(device_map) = self.listeners.get_mut(&workspace_name) {
-      if let Some((target, _)) = device_map.target.as_ref() {
-        println!("Current target: {}, new target {}", target, device_name);
-        // No change in target
-        if target == &device_name {
-          println!("Target device already targetted: {} in {}", device_name, workspace_name);
-          return;
-        }
-        // there is existing target
-        device_map.devices.retain(|device, sender| {
-          sender
-            .send(if device == target {
-              get_target_message(TargetType::OldTarget, &device_name, &clipboard)
-            } else if device == &device_name {
-              get_target_message(TargetType::NewTarget, &device_name, &clipboard)
-            } else {
-              get_target_message(TargetType::Neither, &device_name, &clipboard)
-            })
-            .map_err(|err| {
-              println!("Failed to send event to listener: {:?}", err);
-              err
-            })
-            .is_ok()
-        });
-      } else {
-        println!(
-          "No existing target for workspace: {}. new target: {}",
-          workspace_name, device_name
-        );
-        // No existing target
-        device_map.devices.retain(|device, sender| {
-          sender
-            .send(if device == &device_name {
-              get_target_message(TargetType::NewTarget, &device_name, &clipboard)
-            } else {
-              get_target_message(TargetType::Neither, &device_name, &clipboard)
-            })
-            .map_err(|err| {
-              println!("Failed to send event to listener: {:?}", err);
-              err
-            })
-            .is_ok()
-        });
+  fn span(event: &SubscriptionEvent) -> tracing::Span {
+    match event {
+      SubscriptionEvent::Subscribe(workspace_name, device_name, _)
+      | SubscriptionEvent::Unsubscribe(workspace_name, device_name)
+      | SubscriptionEvent::SubscriptionClosed(workspace_name, device_name, _)
+      | SubscriptionEvent::TargetEvent(workspace_name, device_name, _, _)
+      | SubscriptionEvent::DeviceHeartbeat(workspace_name, device_name) => device_span(workspace_name, device_name),
+      SubscriptionEvent::DownloadRequested(workspace_name, initiate_request) => {
+        device_span(workspace_name, &initiate_request.download_device)
       }
+      SubscriptionEvent::WorkspaceEvent(workspace_name, _)
+      | SubscriptionEvent::WorskpaceClosing(workspace_name)
+      | SubscriptionEvent::Status(workspace_name, _) => workspace_span(workspace_name),
+      SubscriptionEvent::CheckLiveness(_) | SubscriptionEvent::ServerShuttingDown(_) => tracing::Span::none(),
+    }
+  }
 
-      if let Some(sender) = device_map.devices.get(&device_name) {
-        device_map.target = Some((device_name.clone(), sender.clone()));
-      } else {
-        println!("Targetted workspace device is not present {:?}", device_name);
-      }
+  fn stopped(&mut self) {
+    self.listeners.clear();
+    self.presence.clear();
+    self.update_connected_devices();
+  }
+}
+
+impl WorkspaceActor {
+  fn handle_target_event(
+    &mut self,
+    workspace_name: String,
+    device_name: String,
+    clipboard: Option<String>,
+  ) -> Result<Option<ids::DeviceName>, TargetError> {
+    // Only subscribers learn that they are targetted, so nothing else can be a target
+    let device_map = match self.listeners.get_mut(&workspace_name) {
+      Some(device_map) if device_map.devices.contains_key(&device_name) => device_map,
+      _ => return Err(TargetError::NotSubscribed(workspace_name, device_name)),
+    };
+    let previous = device_map.target.as_ref().map(|(target, _)| target.clone());
+    let metrics = &self.metrics;
+    tracing::info!("Current target: {:?}, new target {}", previous, device_name);
+    if previous.as_ref() == Some(&device_name) {
+      tracing::info!("Target device is already targetted");
+      return Ok(previous);
+    }
+
+    device_map.devices.retain(|device, sender| {
+      sender
+        .send(if Some(device) == previous.as_ref() {
+          get_target_message(TargetType::OldTarget, &device_name, &clipboard)
+        } else if device == &device_name {
+          get_target_message(TargetType::NewTarget, &device_name, &clipboard)
+        } else {
+          get_target_message(TargetType::Neither, &device_name, &clipboard)
+        })
+        .map_err(|err| {
+          tracing::warn!(listener = %device, "Failed to send event to listener: {:?}", err);
+          metrics.dropped("subscriber");
+          err
+        })
+        .is_ok()
+    });
+
+    if let Some(sender) = device_map.devices.get(&device_name) {
+      device_map.target = Some((device_name.clone(), sender.clone()));
     } else {
-      println!("Target: No simulation listeners for workspace: {}", workspace_name);
+      tracing::warn!("Targetted workspace device is not present");
+    }
+    Ok(previous)
+  }
+}
+
+// Sends the event to every listener, listeners that have gone away are removed and announced as disconnected
+fn broadcast(
+  device_map: &mut DeviceMap<UnboundedSender<msg::WorkspaceEvent>>,
+  presence: &mut PresenceTable,
+  metrics: &Metrics,
+  workspace_name: &ids::WorkspaceName,
+  event: msg::WorkspaceEvent,
+) {
+  let mut pending = vec![event];
+  while let Some(event) = pending.pop() {
+    presence.record(workspace_name, &event);
+    let mut dropped = vec![];
+    device_map.devices.retain(|device, listener| {
+      if let Err(err) = listener.send(event.clone()) {
+        tracing::warn!(workspace = %workspace_name, listener = %device, "Failed to send event to listener: {:?}", err);
+        metrics.dropped("subscriber");
+        dropped.push(device.clone());
+        return false;
+      }
+      true
+    });
+    for device in dropped {
+      device_map.remove(&device);
+      pending.push(presence_event(&device, msg::DeviceRole::Subscriber, false));
     }
   }
 }
 
+pub(crate) fn presence_event(device: &ids::DeviceName, role: msg::DeviceRole, connected: bool) -> msg::WorkspaceEvent {
+  msg::WorkspaceEvent {
+    event_type: Some(if connected {
+      msg::workspace_event::EventType::DeviceConnected(msg::DeviceConnected {
+        device: device.clone(),
+        role: role.into(),
+      })
+    } else {
+      msg::workspace_event::EventType::DeviceDisconnected(msg::DeviceDisconnected {
+        device: device.clone(),
+        role: role.into(),
+      })
+    }),
+    trace_context: Default::default(),
+  }
+}
+
 enum TargetType {
   NewTarget,
   OldTarget,
@@ -184,5 +429,7 @@ fn get_target_message(
         device: device_name.clone(),
       }),
     }),
+    // Handled in the span of the target request, so the devices continue its trace
+    trace_context: telemetry::current_context(),
   }
 }
@@ -0,0 +1,525 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+use serde::Deserialize;
+use sinnergasm::protos as msg;
+use sinnergasm::telemetry::TraceExport;
+use tonic::metadata::Ascii;
+use tonic::metadata::MetadataValue;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser, Debug)]
+#[command(name = "serve", about = "Relays input and files between the devices of a workspace")]
+pub(crate) struct ServeArgs {
+  // Without a config file the defaults and environment variables are used
+  #[arg(long)]
+  pub(crate) config: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub(crate) enum ConfigError {
+  Read(PathBuf, std::io::Error),
+  Parse(PathBuf, toml::de::Error),
+  InvalidBindAddress(String, std::net::AddrParseError),
+  InvalidMetricsAddress(String, std::net::AddrParseError),
+  MetricsOnBindAddress(SocketAddr),
+  InvalidLogLevel(String, tracing_subscriber::filter::ParseError),
+  InvalidLogFormat(String),
+  ConflictingTraceExports,
+  MissingTlsFile(&'static str, PathBuf, std::io::Error),
+  MissingToken,
+  UnreadableToken(PathBuf, std::io::Error),
+  EmptyToken(String),
+  InvalidToken(String),
+  ConflictingTokenEnvironment,
+  InvalidWorkspace(String),
+  InvalidEnvironment(&'static str, String),
+  InvalidFlag(&'static str, String),
+  InvalidHeartbeat(String),
+}
+
+impl std::fmt::Display for ConfigError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      ConfigError::Read(path, err) => write!(f, "Unable to read config file {:?}: {}", path, err),
+      ConfigError::Parse(path, err) => write!(f, "Unable to parse config file {:?}: {}", path, err),
+      ConfigError::InvalidBindAddress(address, err) => write!(f, "Invalid bind_address {:?}: {}", address, err),
+      ConfigError::InvalidMetricsAddress(address, err) => {
+        write!(f, "Invalid metrics_address {:?}: {}", address, err)
+      }
+      ConfigError::MetricsOnBindAddress(address) => {
+        write!(f, "metrics_address must not be the bind_address {}", address)
+      }
+      ConfigError::InvalidLogLevel(level, err) => write!(f, "Invalid log_level {:?}: {}", level, err),
+      ConfigError::InvalidLogFormat(format) => {
+        write!(f, "Invalid log_format {:?}, expected text or json", format)
+      }
+      ConfigError::ConflictingTraceExports => write!(f, "Set otlp_endpoint or trace_file, not both"),
+      ConfigError::MissingTlsFile(name, path, err) => write!(f, "Unable to read tls.{} {:?}: {}", name, path, err),
+      ConfigError::MissingToken => write!(f, "No token configured, set token.value, token.env or token.file"),
+      ConfigError::UnreadableToken(path, err) => write!(f, "Unable to read token.file {:?}: {}", path, err),
+      ConfigError::EmptyToken(source) => write!(f, "The token from {} is empty", source),
+      ConfigError::InvalidToken(source) => {
+        write!(
+          f,
+          "The token from {} can not be sent in a header, use printable ascii",
+          source
+        )
+      }
+      ConfigError::ConflictingTokenEnvironment => write!(f, "Set SINNERGASM_TOKEN or SINNERGASM_TOKEN_FILE, not both"),
+      ConfigError::InvalidWorkspace(reason) => write!(f, "Invalid initial workspace: {}", reason),
+      ConfigError::InvalidEnvironment(name, value) => write!(f, "{} must be a number, got {:?}", name, value),
+      ConfigError::InvalidFlag(name, value) => write!(f, "{} must be true or false, got {:?}", name, value),
+      ConfigError::InvalidHeartbeat(reason) => write!(f, "Invalid heartbeat settings: {}", reason),
+    }
+  }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+  bind_address: String,
+  // Where prometheus scrapes the metrics over plain http, empty turns the endpoint off
+  metrics_address: String,
+  // A level, or filter directives such as "info,serve::actors=debug"
+  log_level: String,
+  log_format: String,
+  // Spans go to an OTLP collector, or are appended to a file as json, tracing is off without either
+  otlp_endpoint: Option<String>,
+  trace_file: Option<PathBuf>,
+  // Serves grpc reflection so tools like grpcurl can explore the api, behind the same token
+  reflection: bool,
+  storage_path: PathBuf,
+  heartbeat_interval_secs: u64,
+  // Devices that have not sent a heartbeat for this long are evicted
+  liveness_timeout_secs: u64,
+  // Transfers still running when the server is asked to stop get this long to finish
+  shutdown_deadline_secs: u64,
+  tls: TlsSection,
+  token: TokenSection,
+  workspaces: Vec<msg::Workspace>,
+}
+
+impl Default for ConfigFile {
+  fn default() -> Self {
+    Self {
+      bind_address: "0.0.0.0:50051".into(),
+      metrics_address: "0.0.0.0:9100".into(),
+      log_level: "info".into(),
+      log_format: "text".into(),
+      otlp_endpoint: None,
+      trace_file: None,
+      reflection: false,
+      storage_path: "./state/workspaces.json".into(),
+      heartbeat_interval_secs: 5,
+      liveness_timeout_secs: 20,
+      shutdown_deadline_secs: 5,
+      tls: TlsSection::default(),
+      token: TokenSection::default(),
+      workspaces: vec![],
+    }
+  }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct TlsSection {
+  certificate: PathBuf,
+  key: PathBuf,
+}
+
+impl Default for TlsSection {
+  fn default() -> Self {
+    Self {
+      certificate: "keys/server.pem".into(),
+      key: "keys/server.key".into(),
+    }
+  }
+}
+
+// The first source that is set wins, in the order value, env, file.
+// The environment variables replace the whole section.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct TokenSection {
+  value: Option<String>,
+  env: Option<String>,
+  file: Option<PathBuf>,
+}
+
+impl Default for TokenSection {
+  fn default() -> Self {
+    Self {
+      value: None,
+      env: None,
+      file: Some("./keys/token.txt".into()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LogFormat {
+  Text,
+  // One object per line, for log collectors
+  Json,
+}
+
+#[derive(Debug)]
+pub(crate) struct ServerConfig {
+  pub(crate) bind_address: SocketAddr,
+  pub(crate) metrics_address: Option<SocketAddr>,
+  pub(crate) log_filter: EnvFilter,
+  pub(crate) log_format: LogFormat,
+  pub(crate) trace_export: Option<TraceExport>,
+  pub(crate) reflection: bool,
+  pub(crate) storage_path: PathBuf,
+  pub(crate) heartbeat_interval: Duration,
+  pub(crate) liveness_timeout: Duration,
+  pub(crate) shutdown_deadline: Duration,
+  pub(crate) certificate: Vec<u8>,
+  pub(crate) key: Vec<u8>,
+  // The whole authorization header clients send, parsed once so requests never fail to build it
+  pub(crate) authorization: MetadataValue<Ascii>,
+  pub(crate) workspaces: Vec<msg::Workspace>,
+}
+
+impl ServerConfig {
+  pub(crate) fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+    let mut file = match path {
+      Some(path) => {
+        let contents = std::fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+        toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?
+      }
+      None => ConfigFile::default(),
+    };
+    apply_env_overrides(&mut file)?;
+    Self::validate(file)
+  }
+
+  fn validate(file: ConfigFile) -> Result<Self, ConfigError> {
+    let bind_address = file
+      .bind_address
+      .parse()
+      .map_err(|e| ConfigError::InvalidBindAddress(file.bind_address.clone(), e))?;
+    let metrics_address = match file.metrics_address.as_str() {
+      "" => None,
+      address => Some(
+        address
+          .parse()
+          .map_err(|e| ConfigError::InvalidMetricsAddress(file.metrics_address.clone(), e))?,
+      ),
+    };
+    if metrics_address == Some(bind_address) {
+      return Err(ConfigError::MetricsOnBindAddress(bind_address));
+    }
+    let log_filter =
+      EnvFilter::try_new(&file.log_level).map_err(|e| ConfigError::InvalidLogLevel(file.log_level.clone(), e))?;
+    let log_format = match file.log_format.as_str() {
+      "text" => LogFormat::Text,
+      "json" => LogFormat::Json,
+      _ => return Err(ConfigError::InvalidLogFormat(file.log_format.clone())),
+    };
+    let trace_export = match (file.otlp_endpoint, file.trace_file) {
+      (Some(_), Some(_)) => return Err(ConfigError::ConflictingTraceExports),
+      (Some(endpoint), None) => Some(TraceExport::Otlp(endpoint)),
+      (None, Some(path)) => Some(TraceExport::File(path)),
+      (None, None) => None,
+    };
+    let certificate = std::fs::read(&file.tls.certificate)
+      .map_err(|e| ConfigError::MissingTlsFile("certificate", file.tls.certificate.clone(), e))?;
+    let key = std::fs::read(&file.tls.key).map_err(|e| ConfigError::MissingTlsFile("key", file.tls.key.clone(), e))?;
+    let authorization = resolve_token(&file.token)?;
+
+    if file.heartbeat_interval_secs == 0 {
+      return Err(ConfigError::InvalidHeartbeat(
+        "heartbeat_interval_secs must be at least 1".into(),
+      ));
+    }
+    if file.liveness_timeout_secs <= file.heartbeat_interval_secs {
+      return Err(ConfigError::InvalidHeartbeat(format!(
+        "liveness_timeout_secs ({}) must be longer than heartbeat_interval_secs ({})",
+        file.liveness_timeout_secs, file.heartbeat_interval_secs
+      )));
+    }
+
+    let mut names = std::collections::BTreeSet::new();
+    for workspace in file.workspaces.iter() {
+      if workspace.name.is_empty() {
+        return Err(ConfigError::InvalidWorkspace(
+          "workspace names must not be empty".into(),
+        ));
+      }
+      if !names.insert(workspace.name.clone()) {
+        return Err(ConfigError::InvalidWorkspace(format!(
+          "{} is configured more than once",
+          workspace.name
+        )));
+      }
+      for monitor in workspace.monitors.iter() {
+        if !workspace.devices.iter().any(|device| device.name == monitor.device) {
+          return Err(ConfigError::InvalidWorkspace(format!(
+            "monitor {} in {} belongs to unknown device {}",
+            monitor.name, workspace.name, monitor.device
+          )));
+        }
+      }
+    }
+
+    Ok(Self {
+      bind_address,
+      metrics_address,
+      log_filter,
+      log_format,
+      trace_export,
+      reflection: file.reflection,
+      storage_path: file.storage_path,
+      heartbeat_interval: Duration::from_secs(file.heartbeat_interval_secs),
+      liveness_timeout: Duration::from_secs(file.liveness_timeout_secs),
+      shutdown_deadline: Duration::from_secs(file.shutdown_deadline_secs),
+      certificate,
+      key,
+      authorization,
+      workspaces: file.workspaces,
+    })
+  }
+}
+
+// Lets the container image be configured without baking a config file into it
+fn apply_env_overrides(file: &mut ConfigFile) -> Result<(), ConfigError> {
+  if let Ok(bind_address) = std::env::var("SINNERGASM_BIND_ADDRESS") {
+    file.bind_address = bind_address;
+  }
+  if let Ok(metrics_address) = std::env::var("SINNERGASM_METRICS_ADDRESS") {
+    file.metrics_address = metrics_address;
+  }
+  if let Ok(log_level) = std::env::var("SINNERGASM_LOG_LEVEL") {
+    file.log_level = log_level;
+  }
+  if let Ok(log_format) = std::env::var("SINNERGASM_LOG_FORMAT") {
+    file.log_format = log_format;
+  }
+  // Set to an empty value they turn the export from the config file off
+  if let Ok(otlp_endpoint) = std::env::var("SINNERGASM_OTLP_ENDPOINT") {
+    file.otlp_endpoint = Some(otlp_endpoint).filter(|endpoint| !endpoint.is_empty());
+  }
+  if let Ok(trace_file) = std::env::var("SINNERGASM_TRACE_FILE") {
+    file.trace_file = Some(trace_file).filter(|path| !path.is_empty()).map(PathBuf::from);
+  }
+  if let Some(reflection) = env_flag("SINNERGASM_REFLECTION")? {
+    file.reflection = reflection;
+  }
+  if let Ok(storage_path) = std::env::var("SINNERGASM_STORAGE_PATH") {
+    file.storage_path = storage_path.into();
+  }
+  if let Ok(certificate) = std::env::var("SINNERGASM_TLS_CERTIFICATE") {
+    file.tls.certificate = certificate.into();
+  }
+  if let Ok(key) = std::env::var("SINNERGASM_TLS_KEY") {
+    file.tls.key = key.into();
+  }
+  match (
+    std::env::var("SINNERGASM_TOKEN"),
+    std::env::var("SINNERGASM_TOKEN_FILE"),
+  ) {
+    (Ok(_), Ok(_)) => return Err(ConfigError::ConflictingTokenEnvironment),
+    (Ok(token), Err(_)) => {
+      file.token = TokenSection {
+        value: Some(token),
+        env: None,
+        file: None,
+      }
+    }
+    (Err(_), Ok(token_file)) => {
+      file.token = TokenSection {
+        value: None,
+        env: None,
+        file: Some(token_file.into()),
+      }
+    }
+    (Err(_), Err(_)) => {}
+  }
+  if let Some(interval) = env_number("SINNERGASM_HEARTBEAT_INTERVAL_SECS")? {
+    file.heartbeat_interval_secs = interval;
+  }
+  if let Some(timeout) = env_number("SINNERGASM_LIVENESS_TIMEOUT_SECS")? {
+    file.liveness_timeout_secs = timeout;
+  }
+  if let Some(deadline) = env_number("SINNERGASM_SHUTDOWN_DEADLINE_SECS")? {
+    file.shutdown_deadline_secs = deadline;
+  }
+  Ok(())
+}
+
+fn env_number(name: &'static str) -> Result<Option<u64>, ConfigError> {
+  match std::env::var(name) {
+    Ok(value) => value
+      .parse()
+      .map(Some)
+      .map_err(|_| ConfigError::InvalidEnvironment(name, value)),
+    Err(_) => Ok(None),
+  }
+}
+
+fn env_flag(name: &'static str) -> Result<Option<bool>, ConfigError> {
+  match std::env::var(name) {
+    Ok(value) => match value.to_lowercase().as_str() {
+      "1" | "true" | "yes" => Ok(Some(true)),
+      "0" | "false" | "no" => Ok(Some(false)),
+      _ => Err(ConfigError::InvalidFlag(name, value)),
+    },
+    Err(_) => Ok(None),
+  }
+}
+
+fn resolve_token(token: &TokenSection) -> Result<MetadataValue<Ascii>, ConfigError> {
+  let (source, value) = if let Some(value) = &token.value {
+    ("token.value".to_string(), value.clone())
+  } else if let Some(variable) = &token.env {
+    (
+      format!("environment variable {}", variable),
+      std::env::var(variable).unwrap_or_default(),
+    )
+  } else if let Some(path) = &token.file {
+    (
+      format!("{:?}", path),
+      std::fs::read_to_string(path).map_err(|e| ConfigError::UnreadableToken(path.clone(), e))?,
+    )
+  } else {
+    return Err(ConfigError::MissingToken);
+  };
+
+  let value = value.trim();
+  if value.is_empty() {
+    return Err(ConfigError::EmptyToken(source));
+  }
+  // Header values also take bytes past ascii, which clients may refuse to send
+  if !value.bytes().all(|byte| (b' '..=b'~').contains(&byte)) {
+    return Err(ConfigError::InvalidToken(source));
+  }
+  format!("Bearer {}", value)
+    .parse()
+    .map_err(|_| ConfigError::InvalidToken(source))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+
+  // The environment is shared by every test in the process
+  static ENVIRONMENT: Mutex<()> = Mutex::new(());
+
+  fn parse(contents: &str) -> Result<ConfigFile, toml::de::Error> {
+    toml::from_str(contents)
+  }
+
+  fn temporary_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("sinnergasm-config-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+  }
+
+  fn token(value: Option<&str>) -> TokenSection {
+    TokenSection {
+      value: value.map(String::from),
+      env: None,
+      file: None,
+    }
+  }
+
+  #[test]
+  fn rejects_unknown_keys() {
+    assert!(parse("bind_adress = \"0.0.0.0:50051\"").is_err());
+    assert!(parse("[tls]\ncertficate = \"keys/server.pem\"").is_err());
+    assert!(parse("[token]\nvalue = \"secret\"\nvalues = \"secret\"").is_err());
+    assert!(parse("bind_address = \"0.0.0.0:50051\"\n[token]\nvalue = \"secret\"").is_ok());
+  }
+
+  #[test]
+  fn rejects_missing_tls_files() {
+    let mut file = ConfigFile::default();
+    file.tls.certificate = "/nonexistent/server.pem".into();
+    assert!(matches!(
+      ServerConfig::validate(file),
+      Err(ConfigError::MissingTlsFile("certificate", _, _))
+    ));
+
+    let mut file = ConfigFile::default();
+    file.tls.certificate = temporary_file("server.pem", "certificate");
+    file.tls.key = "/nonexistent/server.key".into();
+    assert!(matches!(
+      ServerConfig::validate(file),
+      Err(ConfigError::MissingTlsFile("key", _, _))
+    ));
+  }
+
+  #[test]
+  fn rejects_tokens_that_can_not_be_sent_in_a_header() {
+    assert!(matches!(
+      resolve_token(&token(Some("secret\u{7}"))),
+      Err(ConfigError::InvalidToken(source)) if source == "token.value"
+    ));
+    assert!(matches!(
+      resolve_token(&token(Some("sécret"))),
+      Err(ConfigError::InvalidToken(_))
+    ));
+    assert!(matches!(
+      resolve_token(&token(Some("first\nsecond"))),
+      Err(ConfigError::InvalidToken(_))
+    ));
+    assert!(matches!(
+      resolve_token(&token(Some(" \n"))),
+      Err(ConfigError::EmptyToken(_))
+    ));
+    assert!(matches!(resolve_token(&token(None)), Err(ConfigError::MissingToken)));
+    assert_eq!(resolve_token(&token(Some(" secret\n"))).unwrap(), "Bearer secret");
+  }
+
+  #[test]
+  fn token_environment_variables_win_over_every_other_source() {
+    let _environment = ENVIRONMENT.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    let token_file = temporary_file("token.txt", "from-config-file");
+    let configured = || {
+      let mut file = ConfigFile::default();
+      file.token = TokenSection {
+        value: Some("from-value".into()),
+        env: Some("SINNERGASM_TEST_CONFIGURED_TOKEN".into()),
+        file: Some(token_file.clone()),
+      };
+      file
+    };
+    std::env::set_var("SINNERGASM_TEST_CONFIGURED_TOKEN", "from-configured-env");
+
+    std::env::set_var("SINNERGASM_TOKEN", "from-env");
+    let mut file = configured();
+    apply_env_overrides(&mut file).unwrap();
+    assert_eq!(resolve_token(&file.token).unwrap(), "Bearer from-env");
+
+    std::env::remove_var("SINNERGASM_TOKEN");
+    std::env::set_var(
+      "SINNERGASM_TOKEN_FILE",
+      temporary_file("env-token.txt", "from-env-file"),
+    );
+    let mut file = configured();
+    apply_env_overrides(&mut file).unwrap();
+    assert_eq!(resolve_token(&file.token).unwrap(), "Bearer from-env-file");
+
+    std::env::set_var("SINNERGASM_TOKEN", "from-env");
+    assert!(matches!(
+      apply_env_overrides(&mut configured()),
+      Err(ConfigError::ConflictingTokenEnvironment)
+    ));
+
+    std::env::remove_var("SINNERGASM_TOKEN");
+    std::env::remove_var("SINNERGASM_TOKEN_FILE");
+    let mut file = configured();
+    apply_env_overrides(&mut file).unwrap();
+    assert_eq!(resolve_token(&file.token).unwrap(), "Bearer from-value");
+    std::env::remove_var("SINNERGASM_TEST_CONFIGURED_TOKEN");
+  }
+}
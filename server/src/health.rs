@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use sinnergasm::protos::virtual_workspaces_server::VirtualWorkspacesServer;
+use tonic::server::NamedService;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+use crate::actor::ActorHandle;
+use crate::actors::download_manager::DownloadEvent;
+use crate::actors::simulate::SimulationEvent;
+use crate::actors::workspace::SubscriptionEvent;
+use crate::storage::SaveStatus;
+use crate::workspace_server::WorkspaceServer;
+
+// How soon an actor that stopped for good shows up in the health checks, checking is only a few atomic loads
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// The actors behind the workspaces service, it cannot serve without all of them,
+// nor while the changes made to the workspaces cannot be saved
+pub(crate) struct ServingActors {
+  pub(crate) workspace: ActorHandle<SubscriptionEvent>,
+  pub(crate) simulation: ActorHandle<SimulationEvent>,
+  pub(crate) downloads: ActorHandle<DownloadEvent>,
+  pub(crate) saves: SaveStatus,
+}
+
+impl ServingActors {
+  fn status(&self) -> ServingStatus {
+    let alive = self.workspace.is_alive() && self.simulation.is_alive() && self.downloads.is_alive();
+    if alive && !self.saves.is_failing() {
+      ServingStatus::Serving
+    } else {
+      ServingStatus::NotServing
+    }
+  }
+}
+
+// Sets the status of the workspaces service and of the server as a whole, which the empty name stands for
+pub(crate) async fn set_status(reporter: &mut HealthReporter, status: ServingStatus) {
+  reporter.set_service_status("", status).await;
+  reporter
+    .set_service_status(<VirtualWorkspacesServer<WorkspaceServer> as NamedService>::NAME, status)
+    .await;
+}
+
+// Checks the actors on every interval until the task is aborted
+pub(crate) async fn report_health(mut reporter: HealthReporter, actors: ServingActors) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+  let mut reported = None;
+  loop {
+    interval.tick().await;
+    let status = actors.status();
+    if reported == Some(status) {
+      continue;
+    }
+    if status == ServingStatus::NotServing {
+      tracing::error!("An actor has stopped for good or the workspaces cannot be saved, the server is not serving");
+    }
+    set_status(&mut reporter, status).await;
+    reported = Some(status);
+  }
+}
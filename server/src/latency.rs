@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::common as ids;
+use sinnergasm::latency;
+use sinnergasm::protos as msg;
+
+// The latest latency report of every simulator. Reports count everything since the simulator started,
+// so a newer report replaces the older one.
+#[derive(Debug, Default)]
+pub(crate) struct LatencyTable {
+  reports: BTreeMap<ids::WorkspaceName, BTreeMap<ids::DeviceName, (msg::LatencyReport, SystemTime)>>,
+}
+
+impl LatencyTable {
+  pub(crate) fn record(&mut self, report: msg::LatencyReport) {
+    self
+      .reports
+      .entry(report.workspace.clone())
+      .or_default()
+      .insert(report.device.clone(), (report, SystemTime::now()));
+  }
+
+  pub(crate) fn stats(&self, workspace_name: &ids::WorkspaceName) -> msg::LatencyStats {
+    let mut total = latency::report(workspace_name, "");
+    let devices = self
+      .reports
+      .get(workspace_name)
+      .into_iter()
+      .flatten()
+      .map(|(_, (report, reported_at))| {
+        latency::merge_report(&mut total, report);
+        msg::DeviceLatency {
+          report: Some(report.clone()),
+          reported_at: reported_at
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default(),
+        }
+      })
+      .collect();
+    msg::LatencyStats {
+      devices,
+      total: Some(total),
+    }
+  }
+
+  pub(crate) fn remove_workspace(&mut self, workspace_name: &ids::WorkspaceName) {
+    self.reports.remove(workspace_name);
+  }
+}
@@ -0,0 +1,134 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use hyper::service::make_service_fn;
+use hyper::service::service_fn;
+use hyper::Body;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
+use prometheus::Encoder;
+use prometheus::IntCounter;
+use prometheus::IntCounterVec;
+use prometheus::IntGauge;
+use prometheus::IntGaugeVec;
+use prometheus::Opts;
+use prometheus::Registry;
+use prometheus::TextEncoder;
+
+// What the server is doing, updated by the actors and the request handlers and scraped over http
+pub(crate) struct Metrics {
+  registry: Registry,
+  // Input relayed from a controller to the simulators, by workspace
+  pub(crate) input_events: IntCounterVec,
+  // By role, a device connected with several roles is counted once for each
+  pub(crate) connected_devices: IntGaugeVec,
+  pub(crate) active_downloads: IntGauge,
+  // File data passed from an uploader to a downloader
+  pub(crate) relayed_bytes: IntCounter,
+  // Messages sent to each actor that it has not handled yet
+  pub(crate) queue_depth: IntGaugeVec,
+  // Messages that could not be sent, by the actor or kind of stream they were meant for
+  pub(crate) dropped_sends: IntCounterVec,
+  // Saves of the workspaces that failed, the changes since the last good save are only in memory
+  pub(crate) failed_saves: IntCounter,
+}
+
+impl Metrics {
+  pub(crate) fn new() -> Result<Self, prometheus::Error> {
+    let registry = Registry::new_custom(Some("sinnergasm".into()), None)?;
+    let input_events = IntCounterVec::new(
+      Opts::new("input_events_total", "Input events forwarded to the simulators"),
+      &["workspace"],
+    )?;
+    let connected_devices = IntGaugeVec::new(
+      Opts::new("connected_devices", "Devices connected to a workspace"),
+      &["role"],
+    )?;
+    let active_downloads = IntGauge::new("active_downloads", "File transfers in progress")?;
+    let relayed_bytes = IntCounter::new("relayed_bytes_total", "File data relayed to downloaders")?;
+    let queue_depth = IntGaugeVec::new(
+      Opts::new("actor_queue_depth", "Messages waiting to be handled by an actor"),
+      &["actor"],
+    )?;
+    let dropped_sends = IntCounterVec::new(
+      Opts::new("dropped_sends_total", "Messages that could not be delivered"),
+      &["channel"],
+    )?;
+    let failed_saves = IntCounter::new("failed_saves_total", "Workspace saves that could not be written")?;
+
+    registry.register(Box::new(input_events.clone()))?;
+    registry.register(Box::new(connected_devices.clone()))?;
+    registry.register(Box::new(active_downloads.clone()))?;
+    registry.register(Box::new(relayed_bytes.clone()))?;
+    registry.register(Box::new(queue_depth.clone()))?;
+    registry.register(Box::new(dropped_sends.clone()))?;
+    registry.register(Box::new(failed_saves.clone()))?;
+    Ok(Self {
+      registry,
+      input_events,
+      connected_devices,
+      active_downloads,
+      relayed_bytes,
+      queue_depth,
+      dropped_sends,
+      failed_saves,
+    })
+  }
+
+  pub(crate) fn dropped(&self, channel: &str) {
+    self.dropped_sends.with_label_values(&[channel]).inc();
+  }
+
+  // The counters of a deleted workspace would otherwise be exported forever
+  pub(crate) fn remove_workspace(&self, workspace_name: &str) {
+    let _ = self.input_events.remove_label_values(&[workspace_name]);
+  }
+
+  // In the prometheus text format
+  fn encode(&self) -> Result<Vec<u8>, prometheus::Error> {
+    let mut buffer = vec![];
+    TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+    Ok(buffer)
+  }
+}
+
+impl std::fmt::Debug for Metrics {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Metrics").finish_non_exhaustive()
+  }
+}
+
+// Serves the metrics on /metrics until the task is aborted
+pub(crate) async fn serve_metrics(address: SocketAddr, metrics: Arc<Metrics>) -> Result<(), hyper::Error> {
+  let make_service = make_service_fn(move |_| {
+    let metrics = metrics.clone();
+    async move { Ok::<_, Infallible>(service_fn(move |request| respond(metrics.clone(), request))) }
+  });
+  hyper::Server::try_bind(&address)?.serve(make_service).await
+}
+
+async fn respond(metrics: Arc<Metrics>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+  if request.uri().path() != "/metrics" {
+    return Ok(status(StatusCode::NOT_FOUND));
+  }
+  match metrics.encode() {
+    Ok(body) => Ok(
+      Response::builder()
+        .header(hyper::header::CONTENT_TYPE, TextEncoder::new().format_type())
+        .body(Body::from(body))
+        .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR)),
+    ),
+    Err(err) => {
+      tracing::error!("Unable to encode metrics: {}", err);
+      Ok(status(StatusCode::INTERNAL_SERVER_ERROR))
+    }
+  }
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+  let mut response = Response::new(Body::empty());
+  *response.status_mut() = code;
+  response
+}
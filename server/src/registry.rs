@@ -0,0 +1,382 @@
+use std::collections::BTreeMap;
+
+use crate::common as ids;
+use crate::storage::StorageError;
+use crate::storage::StoreWriter;
+use crate::storage::WorkspaceStore;
+use sinnergasm::protos as msg;
+
+#[derive(Debug)]
+pub(crate) enum RegistryError {
+  WorkspaceExists(ids::WorkspaceName),
+  WorkspaceNotFound(ids::WorkspaceName),
+  DeviceNotFound(ids::WorkspaceName, ids::DeviceName),
+  MonitorTaken(ids::WorkspaceName, String, ids::DeviceName),
+  NotController(ids::WorkspaceName, ids::DeviceName),
+  InvalidName(String),
+}
+
+impl std::fmt::Display for RegistryError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      RegistryError::WorkspaceExists(workspace) => write!(f, "Workspace {} already exists", workspace),
+      RegistryError::WorkspaceNotFound(workspace) => write!(f, "No workspace named {}", workspace),
+      RegistryError::DeviceNotFound(workspace, device) => {
+        write!(f, "No device named {} in workspace {}", device, workspace)
+      }
+      RegistryError::MonitorTaken(workspace, monitor, device) => {
+        write!(
+          f,
+          "Monitor {} in workspace {} already belongs to {}",
+          monitor, workspace, device
+        )
+      }
+      RegistryError::NotController(workspace, device) => {
+        write!(f, "Device {} is not allowed to control workspace {}", device, workspace)
+      }
+      RegistryError::InvalidName(reason) => write!(f, "Invalid name: {}", reason),
+    }
+  }
+}
+
+impl std::error::Error for RegistryError {}
+
+impl From<RegistryError> for tonic::Status {
+  fn from(err: RegistryError) -> Self {
+    match err {
+      RegistryError::WorkspaceExists(_) | RegistryError::MonitorTaken(_, _, _) => {
+        tonic::Status::already_exists(err.to_string())
+      }
+      RegistryError::WorkspaceNotFound(_) | RegistryError::DeviceNotFound(_, _) => {
+        tonic::Status::not_found(err.to_string())
+      }
+      RegistryError::NotController(_, _) => tonic::Status::permission_denied(err.to_string()),
+      RegistryError::InvalidName(_) => tonic::Status::invalid_argument(err.to_string()),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct WorkspaceRegistry {
+  workspaces: BTreeMap<ids::WorkspaceName, msg::Workspace>,
+  writer: StoreWriter,
+}
+
+impl WorkspaceRegistry {
+  pub(crate) fn load(store: &dyn WorkspaceStore, writer: StoreWriter) -> Result<Self, StorageError> {
+    let workspaces = store
+      .load()?
+      .into_iter()
+      .map(|workspace| (workspace.name.clone(), workspace))
+      .collect();
+    Ok(Self { workspaces, writer })
+  }
+
+  pub(crate) fn is_empty(&self) -> bool {
+    self.workspaces.is_empty()
+  }
+
+  // What the simulation actor starts from, it only hears about later changes
+  pub(crate) fn settings(&self) -> BTreeMap<ids::WorkspaceName, msg::WorkspaceSettings> {
+    self
+      .workspaces
+      .values()
+      .map(|workspace| (workspace.name.clone(), workspace.settings.clone().unwrap_or_default()))
+      .collect()
+  }
+
+  pub(crate) fn get(&self, workspace_name: &ids::WorkspaceName) -> Option<&msg::Workspace> {
+    self.workspaces.get(workspace_name)
+  }
+
+  pub(crate) fn device(
+    &self,
+    workspace_name: &ids::WorkspaceName,
+    device_name: &ids::DeviceName,
+  ) -> Result<&msg::Device, RegistryError> {
+    self
+      .get_or_err(workspace_name)?
+      .devices
+      .iter()
+      .find(|device| &device.name == device_name)
+      .ok_or_else(|| RegistryError::DeviceNotFound(workspace_name.clone(), device_name.clone()))
+  }
+
+  // Only devices that joined as, or were configured as, controllers may open a control stream
+  pub(crate) fn check_controller(
+    &self,
+    workspace_name: &ids::WorkspaceName,
+    device_name: &ids::DeviceName,
+  ) -> Result<(), RegistryError> {
+    if !self.device(workspace_name, device_name)?.controller {
+      return Err(RegistryError::NotController(
+        workspace_name.clone(),
+        device_name.clone(),
+      ));
+    }
+    Ok(())
+  }
+
+  pub(crate) fn insert(&mut self, workspace: msg::Workspace) -> Result<(), RegistryError> {
+    if workspace.name.is_empty() {
+      return Err(RegistryError::InvalidName("workspace name must not be empty".into()));
+    }
+    if self.workspaces.contains_key(&workspace.name) {
+      return Err(RegistryError::WorkspaceExists(workspace.name));
+    }
+    self.commit(workspace);
+    Ok(())
+  }
+
+  pub(crate) fn create(&mut self, workspace_name: ids::WorkspaceName) -> Result<(), RegistryError> {
+    self.insert(msg::Workspace {
+      name: workspace_name,
+      controller: "".into(),
+      target: "".into(),
+      devices: vec![],
+      monitors: vec![],
+      settings: None,
+    })
+  }
+
+  pub(crate) fn remove(&mut self, workspace_name: &ids::WorkspaceName) -> Result<msg::Workspace, RegistryError> {
+    let removed = self
+      .workspaces
+      .remove(workspace_name)
+      .ok_or_else(|| RegistryError::WorkspaceNotFound(workspace_name.clone()))?;
+    self.persist();
+    Ok(removed)
+  }
+
+  pub(crate) fn summaries(&self) -> Vec<msg::WorkspaceSummary> {
+    self
+      .workspaces
+      .values()
+      .map(|workspace| msg::WorkspaceSummary {
+        workspace: workspace.name.clone(),
+        number_of_monitors: workspace.monitors.len() as u32,
+        devices: workspace.devices.len() as u32,
+      })
+      .collect()
+  }
+
+  // Applies the whole request or nothing, returning the updated workspace
+  pub(crate) fn configure(&mut self, request: msg::ConfigurationRequest) -> Result<msg::Workspace, RegistryError> {
+    let mut updated = self.get_or_err(&request.workspace)?.clone();
+
+    for device_name in request.removed_devices.iter() {
+      remove_device(&mut updated, device_name);
+    }
+    for device in request.devices.into_iter() {
+      if device.name.is_empty() {
+        return Err(RegistryError::InvalidName("device name must not be empty".into()));
+      }
+      upsert(&mut updated.devices, device, |device| &device.name);
+    }
+
+    for monitor_name in request.removed_monitors.iter() {
+      updated.monitors.retain(|monitor| &monitor.name != monitor_name);
+    }
+    for monitor in request.monitors.into_iter() {
+      if monitor.name.is_empty() {
+        return Err(RegistryError::InvalidName("monitor name must not be empty".into()));
+      }
+      if !has_device(&updated, &monitor.device) {
+        return Err(RegistryError::DeviceNotFound(updated.name, monitor.device));
+      }
+      upsert(&mut updated.monitors, monitor, |monitor| &monitor.name);
+    }
+
+    if let Some(controller) = request.controller {
+      if !controller.is_empty() && !has_device(&updated, &controller) {
+        return Err(RegistryError::DeviceNotFound(updated.name, controller));
+      }
+      for device in updated.devices.iter_mut() {
+        if device.name == controller {
+          device.controller = true;
+        }
+      }
+      updated.controller = controller;
+    }
+
+    if let Some(settings) = request.settings {
+      updated.settings = Some(settings);
+    }
+
+    self.commit(updated.clone());
+    Ok(updated)
+  }
+
+  // Joining again replaces the monitors of the device but keeps its shared files
+  pub(crate) fn join(&mut self, request: msg::JoinRequest) -> Result<msg::Workspace, RegistryError> {
+    if request.device.is_empty() {
+      return Err(RegistryError::InvalidName("device name must not be empty".into()));
+    }
+    let mut updated = self.get_or_err(&request.workspace)?.clone();
+
+    updated.monitors.retain(|monitor| monitor.device != request.device);
+    for mut monitor in request.monitors.into_iter() {
+      if monitor.name.is_empty() {
+        return Err(RegistryError::InvalidName("monitor name must not be empty".into()));
+      }
+      if let Some(existing) = updated.monitors.iter().find(|existing| existing.name == monitor.name) {
+        return Err(RegistryError::MonitorTaken(
+          updated.name,
+          monitor.name,
+          existing.device.clone(),
+        ));
+      }
+      monitor.device = request.device.clone();
+      updated.monitors.push(monitor);
+    }
+
+    if let Some(device) = updated.devices.iter_mut().find(|device| device.name == request.device) {
+      device.controller = request.controller;
+    } else {
+      updated.devices.push(msg::Device {
+        name: request.device.clone(),
+        controller: request.controller,
+        files: vec![],
+      });
+    }
+    if request.controller && updated.controller.is_empty() {
+      updated.controller = request.device;
+    }
+
+    self.commit(updated.clone());
+    Ok(updated)
+  }
+
+  pub(crate) fn leave(&mut self, request: msg::LeaveRequest) -> Result<msg::Workspace, RegistryError> {
+    let mut updated = self.get_or_err(&request.workspace)?.clone();
+    if !has_device(&updated, &request.device) {
+      return Err(RegistryError::DeviceNotFound(updated.name, request.device));
+    }
+    remove_device(&mut updated, &request.device);
+    self.commit(updated.clone());
+    Ok(updated)
+  }
+
+  pub(crate) fn set_controller(
+    &mut self,
+    workspace_name: &ids::WorkspaceName,
+    device_name: &ids::DeviceName,
+  ) -> Result<msg::Workspace, RegistryError> {
+    let mut updated = self.get_or_err(workspace_name)?.clone();
+    find_device(&mut updated, device_name)?.controller = true;
+    updated.controller = device_name.clone();
+    self.commit(updated.clone());
+    Ok(updated)
+  }
+
+  pub(crate) fn share_file(&mut self, request: msg::ShareFileRequest) -> Result<msg::Workspace, RegistryError> {
+    if request.relative_path.is_empty() {
+      return Err(RegistryError::InvalidName("shared file path must not be empty".into()));
+    }
+    let mut updated = self.get_or_err(&request.workspace)?.clone();
+    let device = find_device(&mut updated, &request.device)?;
+    upsert(
+      &mut device.files,
+      msg::SharedFile {
+        relative_path: request.relative_path,
+        size: request.size,
+      },
+      |file| &file.relative_path,
+    );
+    self.commit(updated.clone());
+    Ok(updated)
+  }
+
+  pub(crate) fn remove_shared_file(
+    &mut self,
+    request: msg::RemoveSharedFileRequest,
+  ) -> Result<msg::Workspace, RegistryError> {
+    let mut updated = self.get_or_err(&request.workspace)?.clone();
+    let device = find_device(&mut updated, &request.device)?;
+    device.files.retain(|file| file.relative_path != request.relative_path);
+    self.commit(updated.clone());
+    Ok(updated)
+  }
+
+  fn get_or_err(&self, workspace_name: &ids::WorkspaceName) -> Result<&msg::Workspace, RegistryError> {
+    self
+      .workspaces
+      .get(workspace_name)
+      .ok_or_else(|| RegistryError::WorkspaceNotFound(workspace_name.clone()))
+  }
+
+  // Changes to the live controller or target alone are not written to the store.
+  // Saving happens in the background, failed saves show up in the health checks and metrics.
+  fn commit(&mut self, workspace: msg::Workspace) {
+    let durable_change = !matches!(
+      self.workspaces.get(&workspace.name),
+      Some(existing) if durable(existing) == durable(&workspace)
+    );
+    self.workspaces.insert(workspace.name.clone(), workspace);
+    if durable_change {
+      self.persist();
+    }
+  }
+
+  fn persist(&self) {
+    self.writer.save(self.workspaces.values().map(durable).collect());
+  }
+}
+
+// The configuration of the workspace without who is controlling or targetted right now,
+// which is stale by the time the server restarts
+fn durable(workspace: &msg::Workspace) -> msg::Workspace {
+  msg::Workspace {
+    controller: "".into(),
+    target: "".into(),
+    ..workspace.clone()
+  }
+}
+
+fn find_device<'a>(
+  workspace: &'a mut msg::Workspace,
+  device_name: &ids::DeviceName,
+) -> Result<&'a mut msg::Device, RegistryError> {
+  let workspace_name = workspace.name.clone();
+  workspace
+    .devices
+    .iter_mut()
+    .find(|device| &device.name == device_name)
+    .ok_or_else(|| RegistryError::DeviceNotFound(workspace_name, device_name.clone()))
+}
+
+// Removes the device along with the monitors it owns and any role it held
+fn remove_device(workspace: &mut msg::Workspace, device_name: &ids::DeviceName) {
+  workspace.devices.retain(|device| &device.name != device_name);
+  workspace.monitors.retain(|monitor| &monitor.device != device_name);
+  if &workspace.controller == device_name {
+    workspace.controller = "".into();
+  }
+  if &workspace.target == device_name {
+    workspace.target = "".into();
+  }
+}
+
+fn has_device(workspace: &msg::Workspace, device_name: &ids::DeviceName) -> bool {
+  workspace.devices.iter().any(|device| &device.name == device_name)
+}
+
+fn upsert<T>(items: &mut Vec<T>, item: T, name: impl Fn(&T) -> &String) {
+  if let Some(existing) = items.iter_mut().find(|existing| name(existing) == name(&item)) {
+    *existing = item;
+  } else {
+    items.push(item);
+  }
+}
+
+// Devices and monitors are added as machines join
+pub(crate) fn default_workspace() -> msg::Workspace {
+  msg::Workspace {
+    name: "The Workspace".to_string(),
+    controller: "".to_string(),
+    target: "".to_string(), // Why can't this be None?
+    devices: vec![],
+    monitors: vec![],
+    settings: None,
+  }
+}
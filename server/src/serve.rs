@@ -1,10 +1,19 @@
 // pub mod workspace;
-// pub mod actor;
+pub mod actor;
 pub mod actors;
 pub mod common;
+pub mod config;
 pub mod events;
+pub mod health;
+pub mod latency;
+pub mod metrics;
+pub mod registry;
+pub mod sessions;
+pub mod storage;
 pub mod workspace_server;
 
+use crate::actor::ActorHandle;
+use crate::actor::ActorSystem;
 use crate::actors::simulate::SimulationActor;
 use crate::actors::simulate::SimulationEvent;
 use crate::actors::workspace::SubscriptionEvent;
@@ -13,100 +22,243 @@ use crate::actors::workspace::WorkspaceActor;
 use actors::download_manager::DownloadEvent;
 use actors::download_manager::DownloadsActor;
 use tonic::transport::Server;
-use tonic::{metadata::MetadataValue, Request, Status};
+use tonic::{Request, Status};
 
-use sinnergasm::options::read_token;
-use sinnergasm::options::PORT;
 use sinnergasm::protos::virtual_workspaces_server::VirtualWorkspacesServer;
-use tokio::sync::mpsc as tokio_mpsc;
 use tonic_health::ServingStatus;
 
+use crate::config::LogFormat;
+use crate::config::ServeArgs;
+use crate::config::ServerConfig;
+use crate::health::ServingActors;
+use crate::metrics::Metrics;
+use crate::registry::default_workspace;
+use crate::registry::WorkspaceRegistry;
+use crate::sessions::ControlSessions;
+use crate::storage::JsonFileStore;
+use crate::storage::StoreWriter;
 use crate::workspace_server::WorkspaceServer;
 
+use clap::Parser;
+use sinnergasm::telemetry;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tonic::codegen::InterceptedService;
 use tonic::transport::Identity;
-use tracing::Level;
-use tracing_subscriber::FmtSubscriber;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 10)]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-  let subscriber = FmtSubscriber::builder().with_max_level(Level::INFO).finish();
-  tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
-
-  let (workspace_send, mut workspace_recv) = tokio_mpsc::unbounded_channel::<SubscriptionEvent>();
-  let workspace_task = tokio::task::spawn(async move {
-    let mut workspace_actor = WorkspaceActor::default();
-    while let Some(event) = workspace_recv.recv().await {
-      if matches!(event, SubscriptionEvent::ApplicationClosing) {
-        break;
-      }
-      workspace_actor.receive(event);
+  let args = ServeArgs::parse();
+  let config = match ServerConfig::load(args.config.as_deref()) {
+    Ok(config) => config,
+    Err(err) => {
+      eprintln!("Invalid server configuration: {}", err);
+      std::process::exit(2);
     }
-  });
+  };
 
-  let (sim_send, mut sim_recv) = tokio_mpsc::unbounded_channel::<SimulationEvent>();
-  let replication_task = tokio::task::spawn(async move {
-    let mut simulation_actor = SimulationActor::default();
-    while let Some(event) = sim_recv.recv().await {
-      if matches!(event, SimulationEvent::ApplicationClosing) {
-        break;
+  let format = match config.log_format {
+    LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+    LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed(),
+  };
+  let telemetry = match &config.trace_export {
+    Some(export) => Some(telemetry::layer("server", export)?),
+    None => None,
+  };
+  tracing_subscriber::registry()
+    .with(config.log_filter)
+    .with(format)
+    .with(telemetry)
+    .init();
+
+  let metrics = Arc::new(Metrics::new()?);
+  let metrics_task = config.metrics_address.map(|address| {
+    let metrics = metrics.clone();
+    tokio::task::spawn(async move {
+      if let Err(err) = metrics::serve_metrics(address, metrics).await {
+        tracing::error!("Unable to serve metrics on {}: {}", address, err);
       }
-      simulation_actor.receive(event);
+    })
+  });
+
+  let store = Arc::new(JsonFileStore::new(config.storage_path.clone()));
+  let (store_writer, save_status, store_task) = StoreWriter::spawn(store.clone(), metrics.clone());
+  let mut registry = WorkspaceRegistry::load(store.as_ref(), store_writer)?;
+  // Configured workspaces only seed the store, changes made at runtime win over the config file
+  for workspace in config.workspaces.iter() {
+    if registry.get(&workspace.name).is_none() {
+      registry.insert(workspace.clone())?;
     }
+  }
+  if registry.is_empty() {
+    registry.insert(default_workspace())?;
+  }
+  let registry = Arc::new(RwLock::new(registry));
+
+  // Actors that others depend on are spawned first, they are stopped last
+  let mut actors = ActorSystem::new(metrics.clone());
+  let workspace_metrics = metrics.clone();
+  let workspace_send = actors.spawn("workspace", move || WorkspaceActor::new(workspace_metrics.clone()));
+  let sim_workspace_send = workspace_send.clone();
+  let sim_metrics = metrics.clone();
+  let sim_registry = registry.clone();
+  let sim_send = actors.spawn("simulation", move || {
+    // Read again on every restart, the settings applied to the actor that panicked went with it
+    let settings = match sim_registry.read() {
+      Ok(registry) => registry.settings(),
+      Err(_) => {
+        tracing::error!("Workspace registry is poisoned, simulating with the default settings");
+        BTreeMap::new()
+      }
+    };
+    SimulationActor::new(sim_workspace_send.clone(), settings, sim_metrics.clone())
   });
+  let download_metrics = metrics.clone();
+  let download_send = actors.spawn("downloads", move || DownloadsActor::new(download_metrics.clone()));
 
-  let (download_send, mut download_receive) = tokio_mpsc::unbounded_channel::<DownloadEvent>();
-  let download_task = tokio::task::spawn(async move {
-    let mut download_manager = DownloadsActor::default();
-    while let Some(event) = download_receive.recv().await {
-      if matches!(event, DownloadEvent::ApplicationClosing) {
+  // Drives the heartbeats on every stream and the eviction of silent devices
+  let liveness_workspace_send = workspace_send.clone();
+  let liveness_sim_send = sim_send.clone();
+  let (heartbeat_interval, liveness_timeout) = (config.heartbeat_interval, config.liveness_timeout);
+  let liveness_task = tokio::task::spawn(async move {
+    let mut interval = tokio::time::interval(heartbeat_interval);
+    loop {
+      interval.tick().await;
+      if liveness_workspace_send
+        .send(SubscriptionEvent::CheckLiveness(liveness_timeout))
+        .is_err()
+        || liveness_sim_send
+          .send(SimulationEvent::CheckLiveness(liveness_timeout))
+          .is_err()
+      {
         break;
       }
-      download_manager.receive(event);
     }
   });
 
-  let token = read_token();
-  let check_auth = move |req: Request<()>| {
-    let metadata: MetadataValue<_> = format!("Bearer {}", token).parse().unwrap();
-    match req.metadata().get("authorization") {
-      Some(t) if metadata == t => Ok(req),
-      _ => Err(Status::unauthenticated("No valid auth token")),
-    }
+  let authorization = config.authorization.clone();
+  let check_auth = move |req: Request<()>| match req.metadata().get("authorization") {
+    Some(t) if authorization == t => Ok(req),
+    _ => Err(Status::unauthenticated("No valid auth token")),
   };
 
-  let (mut health_reporter, _health_service) = tonic_health::server::health_reporter();
-  health_reporter
-    .set_service_status("virtualworkspaces.VirtualWorkspaces", ServingStatus::Serving)
-    .await;
-
-  // health_reporter
-  //   // .set_serving::<tonic::service::interceptor::InterceptedService<Self, VirtualWorkspacesServer<WorkspaceServer>>>()
-  //   .set_serving::<VirtualWorkspacesServer<WorkspaceServer>>()
-  //   .await;
-  // health_reporter.set_serving::<VirtualWorkspacesServer<WorkspaceServer>>().await;
-
-  let cert = std::fs::read("keys/server.pem").expect("Missing server.pem");
-  let key = std::fs::read("keys/server.key").expect("Missing server.key");
-  let addr = format!("0.0.0.0:{}", PORT).parse()?;
-  let server = WorkspaceServer::new(workspace_send.clone(), sim_send.clone(), download_send.clone());
+  // Not behind the token, so the container runtime and load balancers can probe it
+  let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+  let health_task = tokio::task::spawn(health::report_health(
+    health_reporter.clone(),
+    ServingActors {
+      workspace: workspace_send.clone(),
+      simulation: sim_send.clone(),
+      downloads: download_send.clone(),
+      saves: save_status,
+    },
+  ));
+
+  let control_sessions = Arc::new(Mutex::new(ControlSessions::default()));
+  let server = WorkspaceServer::new(
+    workspace_send.clone(),
+    sim_send.clone(),
+    download_send.clone(),
+    registry,
+    control_sessions.clone(),
+    metrics,
+  );
+  let reflection_service = if config.reflection {
+    let reflection = tonic_reflection::server::Builder::configure()
+      .register_encoded_file_descriptor_set(sinnergasm::protos::FILE_DESCRIPTOR_SET)
+      .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+      .build()?;
+    Some(InterceptedService::new(reflection, check_auth.clone()))
+  } else {
+    None
+  };
   let service = VirtualWorkspacesServer::with_interceptor(server, check_auth);
+
+  // The server waits for every open stream to end before it returns, so the actors are stopped
+  // as part of the shutdown signal: that is what ends the streams they hold.
+  let shutdown_deadline = config.shutdown_deadline;
+  let shutdown = async move {
+    shutdown_signal().await;
+    tracing::info!("Shutting down, transfers have {:?} to finish", shutdown_deadline);
+    // Load balancers stop sending new clients while the transfers drain
+    health_task.abort();
+    health::set_status(&mut health_reporter, ServingStatus::NotServing).await;
+    liveness_task.abort();
+    if workspace_send
+      .send(SubscriptionEvent::ServerShuttingDown(shutdown_deadline))
+      .is_err()
+    {
+      tracing::error!("Unable to tell subscribers that the server is shutting down");
+    }
+    match control_sessions.lock() {
+      Ok(mut sessions) => sessions.end_all(),
+      Err(_) => tracing::error!("Control sessions are poisoned, control streams are left open"),
+    }
+    drain_downloads(&download_send, shutdown_deadline).await;
+    actors.shutdown().await;
+    tracing::info!("All actors have stopped");
+    // Kept up until now so the drain can be watched
+    if let Some(metrics_task) = metrics_task {
+      metrics_task.abort();
+    }
+  };
+
   Server::builder()
-    .tls_config(tonic::transport::ServerTlsConfig::new().identity(Identity::from_pem(&cert, &key)))?
-    // .add_service(health_service)
+    .tls_config(
+      tonic::transport::ServerTlsConfig::new().identity(Identity::from_pem(&config.certificate, &config.key)),
+    )?
+    .add_service(health_service)
     .add_service(service)
-    .serve(addr)
+    .add_optional_service(reflection_service)
+    .serve_with_shutdown(config.bind_address, shutdown)
     .await?;
 
-  sim_send.send(SimulationEvent::ApplicationClosing)?;
-  workspace_send.send(SubscriptionEvent::ApplicationClosing)?;
+  // The registry went away with the server, so the writer only has the last changes left to save
+  if tokio::time::timeout(shutdown_deadline, store_task).await.is_err() {
+    tracing::error!("Workspaces were not saved within {:?}", shutdown_deadline);
+  }
+
+  telemetry::shutdown();
+  Ok(())
+}
 
-  drop(sim_send);
-  drop(workspace_send);
-  drop(download_send);
+// Resolves on SIGINT, or on SIGTERM where there is one, which is how containers are stopped
+async fn shutdown_signal() {
+  let interrupt = async {
+    if let Err(err) = tokio::signal::ctrl_c().await {
+      tracing::error!("Unable to listen for SIGINT: {}", err);
+      std::future::pending::<()>().await;
+    }
+  };
+  #[cfg(unix)]
+  let terminate = async {
+    match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+      Ok(mut terminate) => {
+        terminate.recv().await;
+      }
+      Err(err) => {
+        tracing::error!("Unable to listen for SIGTERM: {}", err);
+        std::future::pending::<()>().await;
+      }
+    }
+  };
+  #[cfg(not(unix))]
+  let terminate = std::future::pending::<()>();
 
-  workspace_task.await?;
-  replication_task.await?;
-  download_task.await?;
+  tokio::select! {
+    _ = interrupt => {}
+    _ = terminate => {}
+  }
+}
 
-  Ok(())
+async fn drain_downloads(download_send: &ActorHandle<DownloadEvent>, deadline: Duration) {
+  match tokio::time::timeout(deadline, download_send.request(DownloadEvent::Drain)).await {
+    Ok(Ok(())) => tracing::info!("All transfers have finished"),
+    Ok(Err(err)) => tracing::error!("Unable to drain transfers: {}", err),
+    Err(_) => tracing::warn!("Transfers did not finish within {:?}, they are cut off", deadline),
+  }
 }
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+
+use tokio::sync::oneshot;
+
+use crate::common as ids;
+
+#[derive(Debug)]
+pub(crate) enum SessionError {
+  AlreadyControlled(ids::WorkspaceName, ids::DeviceName),
+}
+
+impl std::fmt::Display for SessionError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      SessionError::AlreadyControlled(workspace, device) => {
+        write!(f, "Workspace {} is already controlled by {}", workspace, device)
+      }
+    }
+  }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<SessionError> for tonic::Status {
+  fn from(err: SessionError) -> Self {
+    match err {
+      SessionError::AlreadyControlled(_, _) => tonic::Status::failed_precondition(err.to_string()),
+    }
+  }
+}
+
+// Why an open control stream was ended
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Revoked {
+  HandedOver,
+  ShuttingDown,
+}
+
+// The open control stream of the lease holder
+#[derive(Debug)]
+struct ControlStream {
+  id: u64,
+  revoke: oneshot::Sender<Revoked>,
+}
+
+#[derive(Debug)]
+struct ControlLease {
+  device: ids::DeviceName,
+  stream: Option<ControlStream>,
+}
+
+impl ControlLease {
+  // Ends the open control stream, if any
+  fn revoke(self, reason: Revoked) {
+    if let Some(stream) = self.stream {
+      // The stream may already be gone
+      let _ = stream.revoke.send(reason);
+    }
+  }
+}
+
+// The attached stream and whether the lease changed hands to get it
+pub(crate) struct AttachedStream {
+  pub(crate) id: u64,
+  pub(crate) revoked: oneshot::Receiver<Revoked>,
+  pub(crate) granted: bool,
+}
+
+// One controller lease per workspace. The holder keeps the lease between control streams,
+// so another device can only take it over through a handover.
+#[derive(Debug, Default)]
+pub(crate) struct ControlSessions {
+  next_id: u64,
+  leases: BTreeMap<ids::WorkspaceName, ControlLease>,
+}
+
+impl ControlSessions {
+  // Grants a free lease to the device, or attaches a new stream for the current holder
+  pub(crate) fn attach(
+    &mut self,
+    workspace_name: &ids::WorkspaceName,
+    device_name: &ids::DeviceName,
+  ) -> Result<AttachedStream, SessionError> {
+    let granted = match self.leases.get(workspace_name) {
+      Some(lease) if &lease.device != device_name => {
+        return Err(SessionError::AlreadyControlled(
+          workspace_name.clone(),
+          lease.device.clone(),
+        ));
+      }
+      Some(_) => false,
+      None => true,
+    };
+
+    self.next_id += 1;
+    let (revoke, revoked) = oneshot::channel();
+    // Replacing an older stream of the same device revokes it
+    if let Some(replaced) = self.leases.insert(
+      workspace_name.clone(),
+      ControlLease {
+        device: device_name.clone(),
+        stream: Some(ControlStream {
+          id: self.next_id,
+          revoke,
+        }),
+      },
+    ) {
+      replaced.revoke(Revoked::HandedOver);
+    }
+    Ok(AttachedStream {
+      id: self.next_id,
+      revoked,
+      granted,
+    })
+  }
+
+  // The holder keeps the lease after its stream ends
+  pub(crate) fn detach(&mut self, workspace_name: &ids::WorkspaceName, id: u64) {
+    if let Some(lease) = self.leases.get_mut(workspace_name) {
+      if matches!(&lease.stream, Some(stream) if stream.id == id) {
+        lease.stream = None;
+      }
+    }
+  }
+
+  // Moves the lease to the device, returning the previous holder, none when the device already holds it.
+  // A holder with an open stream only gives the lease up when forced.
+  pub(crate) fn handover(
+    &mut self,
+    workspace_name: &ids::WorkspaceName,
+    device_name: &ids::DeviceName,
+    force: bool,
+  ) -> Result<Option<ids::DeviceName>, SessionError> {
+    if let Some(lease) = self.leases.get(workspace_name) {
+      if &lease.device == device_name {
+        return Ok(None);
+      }
+      if lease.stream.is_some() && !force {
+        return Err(SessionError::AlreadyControlled(
+          workspace_name.clone(),
+          lease.device.clone(),
+        ));
+      }
+    }
+    let previous = self.leases.insert(
+      workspace_name.clone(),
+      ControlLease {
+        device: device_name.clone(),
+        stream: None,
+      },
+    );
+    Ok(previous.map(|lease| {
+      let device = lease.device.clone();
+      lease.revoke(Revoked::HandedOver);
+      device
+    }))
+  }
+
+  pub(crate) fn end_device(&mut self, workspace_name: &ids::WorkspaceName, device_name: &ids::DeviceName) {
+    if matches!(self.leases.get(workspace_name), Some(lease) if &lease.device == device_name) {
+      if let Some(lease) = self.leases.remove(workspace_name) {
+        lease.revoke(Revoked::HandedOver);
+      }
+    }
+  }
+
+  pub(crate) fn end_workspace(&mut self, workspace_name: &ids::WorkspaceName) {
+    if let Some(lease) = self.leases.remove(workspace_name) {
+      lease.revoke(Revoked::HandedOver);
+    }
+  }
+
+  // Ends every open control stream, the leases do not outlive the server anyway
+  pub(crate) fn end_all(&mut self) {
+    for (_, lease) in std::mem::take(&mut self.leases) {
+      lease.revoke(Revoked::ShuttingDown);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::sync::oneshot::error::TryRecvError;
+
+  fn name(name: &str) -> String {
+    name.to_string()
+  }
+
+  #[test]
+  fn another_device_cannot_take_an_open_stream_without_force() {
+    let mut sessions = ControlSessions::default();
+    let mut first = sessions.attach(&name("workspace"), &name("laptop")).unwrap();
+    assert!(first.granted);
+
+    assert!(matches!(
+      sessions.attach(&name("workspace"), &name("desktop")),
+      Err(SessionError::AlreadyControlled(_, device)) if device == "laptop"
+    ));
+    assert!(matches!(
+      sessions.handover(&name("workspace"), &name("desktop"), false),
+      Err(SessionError::AlreadyControlled(_, device)) if device == "laptop"
+    ));
+    assert!(matches!(first.revoked.try_recv(), Err(TryRecvError::Empty)));
+  }
+
+  #[test]
+  fn a_forced_handover_revokes_the_open_stream() {
+    let mut sessions = ControlSessions::default();
+    let mut first = sessions.attach(&name("workspace"), &name("laptop")).unwrap();
+
+    let previous = sessions.handover(&name("workspace"), &name("desktop"), true).unwrap();
+    assert_eq!(previous.as_deref(), Some("laptop"));
+    assert!(matches!(first.revoked.try_recv(), Ok(Revoked::HandedOver)));
+
+    // The lease is already the new holder's, its first stream attaches to it
+    let second = sessions.attach(&name("workspace"), &name("desktop")).unwrap();
+    assert!(!second.granted);
+  }
+
+  #[test]
+  fn a_handover_to_the_holder_changes_nothing() {
+    let mut sessions = ControlSessions::default();
+    let mut first = sessions.attach(&name("workspace"), &name("laptop")).unwrap();
+
+    let previous = sessions.handover(&name("workspace"), &name("laptop"), true).unwrap();
+    assert_eq!(previous, None);
+    assert!(matches!(first.revoked.try_recv(), Err(TryRecvError::Empty)));
+  }
+
+  #[test]
+  fn ending_a_device_only_ends_its_own_lease() {
+    let mut sessions = ControlSessions::default();
+    let mut laptop = sessions.attach(&name("workspace"), &name("laptop")).unwrap();
+    let mut desktop = sessions.attach(&name("other"), &name("desktop")).unwrap();
+
+    sessions.end_device(&name("workspace"), &name("desktop"));
+    assert!(matches!(laptop.revoked.try_recv(), Err(TryRecvError::Empty)));
+    assert!(matches!(desktop.revoked.try_recv(), Err(TryRecvError::Empty)));
+
+    sessions.end_device(&name("workspace"), &name("laptop"));
+    assert!(matches!(laptop.revoked.try_recv(), Ok(Revoked::HandedOver)));
+    assert!(matches!(desktop.revoked.try_recv(), Err(TryRecvError::Empty)));
+    assert!(sessions.attach(&name("workspace"), &name("tablet")).unwrap().granted);
+  }
+}
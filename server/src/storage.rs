@@ -0,0 +1,215 @@
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::metrics::Metrics;
+use serde::Deserialize;
+use serde::Serialize;
+use sinnergasm::protos as msg;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+// Bump this when the stored layout changes in a way older servers cannot read
+const STORAGE_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub(crate) enum StorageError {
+  Io(PathBuf, std::io::Error),
+  Format(PathBuf, serde_json::Error),
+  UnsupportedVersion(PathBuf, u32),
+}
+
+impl std::fmt::Display for StorageError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      StorageError::Io(path, err) => write!(f, "Unable to access {:?}: {}", path, err),
+      StorageError::Format(path, err) => write!(f, "Unable to parse {:?}: {}", path, err),
+      StorageError::UnsupportedVersion(path, version) => write!(
+        f,
+        "{:?} has storage version {}, this server supports up to {}",
+        path, version, STORAGE_VERSION
+      ),
+    }
+  }
+}
+
+impl std::error::Error for StorageError {}
+
+pub(crate) trait WorkspaceStore: Debug + Send + Sync {
+  fn load(&self) -> Result<Vec<msg::Workspace>, StorageError>;
+  fn save(&self, workspaces: &[msg::Workspace]) -> Result<(), StorageError>;
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredWorkspaces {
+  version: u32,
+  workspaces: Vec<msg::Workspace>,
+}
+
+#[derive(Debug)]
+pub(crate) struct JsonFileStore {
+  path: PathBuf,
+}
+
+impl JsonFileStore {
+  pub(crate) fn new(path: impl Into<PathBuf>) -> Self {
+    Self { path: path.into() }
+  }
+}
+
+impl WorkspaceStore for JsonFileStore {
+  fn load(&self) -> Result<Vec<msg::Workspace>, StorageError> {
+    if !self.path.exists() {
+      return Ok(vec![]);
+    }
+    let contents = std::fs::read_to_string(&self.path).map_err(|e| StorageError::Io(self.path.clone(), e))?;
+    let stored: StoredWorkspaces =
+      serde_json::from_str(&contents).map_err(|e| StorageError::Format(self.path.clone(), e))?;
+    if stored.version > STORAGE_VERSION {
+      return Err(StorageError::UnsupportedVersion(self.path.clone(), stored.version));
+    }
+    Ok(stored.workspaces)
+  }
+
+  fn save(&self, workspaces: &[msg::Workspace]) -> Result<(), StorageError> {
+    if let Some(directory) = self.path.parent() {
+      std::fs::create_dir_all(directory).map_err(|e| StorageError::Io(directory.to_path_buf(), e))?;
+    }
+    let stored = StoredWorkspaces {
+      version: STORAGE_VERSION,
+      workspaces: workspaces.to_vec(),
+    };
+    let contents = serde_json::to_string_pretty(&stored).map_err(|e| StorageError::Format(self.path.clone(), e))?;
+
+    // Write next to the real file and rename so a crash never leaves a half written file behind
+    let temporary = self.path.with_extension("tmp");
+    std::fs::write(&temporary, contents).map_err(|e| StorageError::Io(temporary.clone(), e))?;
+    std::fs::rename(&temporary, &self.path).map_err(|e| StorageError::Io(self.path.clone(), e))?;
+    Ok(())
+  }
+}
+
+// Whether the last save failed, the server reports itself as not serving until a save succeeds again
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SaveStatus(Arc<AtomicBool>);
+
+impl SaveStatus {
+  pub(crate) fn is_failing(&self) -> bool {
+    self.0.load(Ordering::Relaxed)
+  }
+
+  fn record(&self, failed: bool) {
+    self.0.store(failed, Ordering::Relaxed);
+  }
+}
+
+// Saves the workspaces on a blocking thread so no handler waits on the disk.
+// Snapshots queued while a save runs are skipped for the newest one.
+#[derive(Debug, Clone)]
+pub(crate) struct StoreWriter {
+  sender: mpsc::UnboundedSender<Vec<msg::Workspace>>,
+}
+
+impl StoreWriter {
+  // The task ends once every writer is dropped and the last snapshot is saved
+  pub(crate) fn spawn(store: Arc<dyn WorkspaceStore>, metrics: Arc<Metrics>) -> (Self, SaveStatus, JoinHandle<()>) {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<Vec<msg::Workspace>>();
+    let status = SaveStatus::default();
+    let task_status = status.clone();
+    let task = tokio::task::spawn(async move {
+      while let Some(mut workspaces) = receiver.recv().await {
+        while let Ok(newer) = receiver.try_recv() {
+          workspaces = newer;
+        }
+        let store = store.clone();
+        let failed = match tokio::task::spawn_blocking(move || store.save(&workspaces)).await {
+          Ok(Ok(())) => {
+            tracing::debug!("Saved workspaces");
+            false
+          }
+          Ok(Err(err)) => {
+            tracing::error!("Unable to save workspaces: {}", err);
+            true
+          }
+          Err(err) => {
+            tracing::error!("Saving workspaces failed: {}", err);
+            true
+          }
+        };
+        if failed {
+          metrics.failed_saves.inc();
+        }
+        task_status.record(failed);
+      }
+    });
+    (Self { sender }, status, task)
+  }
+
+  pub(crate) fn save(&self, workspaces: Vec<msg::Workspace>) {
+    if self.sender.send(workspaces).is_err() {
+      tracing::error!("Workspace writer has stopped, changes are not saved");
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::registry::default_workspace;
+
+  // A directory of its own for every test, so they can run in parallel
+  fn storage_path(test: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("sinnergasm-storage-{}-{}", std::process::id(), test));
+    let _ = std::fs::remove_dir_all(&directory);
+    directory.join("workspaces.json")
+  }
+
+  #[test]
+  fn loads_what_was_saved() {
+    let store = JsonFileStore::new(storage_path("round-trip"));
+    let mut workspace = default_workspace();
+    workspace.devices.push(msg::Device {
+      name: "laptop".into(),
+      controller: true,
+      files: vec![msg::SharedFile {
+        relative_path: "notes.txt".into(),
+        size: Some(12),
+      }],
+    });
+
+    store.save(&[workspace.clone()]).unwrap();
+    assert_eq!(store.load().unwrap(), vec![workspace]);
+  }
+
+  #[test]
+  fn loads_nothing_before_the_first_save() {
+    let store = JsonFileStore::new(storage_path("missing"));
+    assert!(store.load().unwrap().is_empty());
+  }
+
+  #[test]
+  fn refuses_a_newer_version() {
+    let path = storage_path("version");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, r#"{"version": 99, "workspaces": []}"#).unwrap();
+
+    let err = JsonFileStore::new(path).load().unwrap_err();
+    assert!(matches!(err, StorageError::UnsupportedVersion(_, 99)), "{}", err);
+  }
+
+  #[test]
+  fn writes_a_temporary_file_and_renames_it() {
+    let path = storage_path("rename");
+    let store = JsonFileStore::new(path.clone());
+    store.save(&[default_workspace()]).unwrap();
+    assert!(path.exists());
+    assert!(!path.with_extension("tmp").exists());
+
+    // A write that fails leaves the saved file as it was
+    std::fs::create_dir(path.with_extension("tmp")).unwrap();
+    assert!(store.save(&[]).is_err());
+    assert_eq!(store.load().unwrap(), vec![default_workspace()]);
+  }
+}
@@ -1,24 +1,93 @@
 use futures::stream::StreamExt;
 use tokio::sync::mpsc;
 
+use crate::actor::ActorError;
+use crate::actor::ActorHandle;
+use crate::actors::device_map::StreamSender;
 use crate::actors::download_manager::{DownloadEvent, DownloadKey};
+use crate::actors::input_queue::input_queue;
+use crate::actors::input_queue::OverflowPolicy;
+use crate::actors::input_queue::QueueError;
 use crate::actors::simulate::SimulationEvent;
-use crate::actors::workspace::{SubscriptionEvent, self};
+use crate::actors::workspace::presence_event;
+use crate::actors::workspace::LiveStatus;
+use crate::actors::workspace::SubscriptionEvent;
+use crate::actors::workspace::TargetError;
+use crate::common as ids;
+use crate::latency::LatencyTable;
+use crate::metrics::Metrics;
+use crate::registry::RegistryError;
+use crate::registry::WorkspaceRegistry;
+use crate::sessions::ControlSessions;
+use crate::sessions::Revoked;
+use sinnergasm::latency::now_us;
 use sinnergasm::protos as msg;
 use sinnergasm::protos::virtual_workspaces_server::VirtualWorkspaces;
+use sinnergasm::telemetry;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tracing::Instrument;
 
-type SimulationSender = tokio::sync::mpsc::UnboundedSender<SimulationEvent>;
-type WorkspaceSender = tokio::sync::mpsc::UnboundedSender<SubscriptionEvent>;
-type DownloadSender = tokio::sync::mpsc::UnboundedSender<DownloadEvent>;
+type SimulationSender = ActorHandle<SimulationEvent>;
+type WorkspaceSender = ActorHandle<SubscriptionEvent>;
+type DownloadSender = ActorHandle<DownloadEvent>;
+
+// What the helpers of the server fail with, turned into a status at the rpc boundary
+// since a status is too large to pass around in every result
+#[derive(Debug)]
+pub(crate) enum ServerError {
+  Poisoned(&'static str),
+  Registry(RegistryError),
+  Actor(ActorError),
+  // The subscribers could not be told about the change
+  Broadcast(ActorError),
+}
+
+impl std::fmt::Display for ServerError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      ServerError::Poisoned(name) => write!(f, "{} is poisoned", name),
+      ServerError::Registry(err) => write!(f, "{}", err),
+      ServerError::Actor(err) | ServerError::Broadcast(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+impl std::error::Error for ServerError {}
+
+impl From<ServerError> for tonic::Status {
+  fn from(err: ServerError) -> Self {
+    match err {
+      ServerError::Poisoned(_) => tonic::Status::internal(err.to_string()),
+      ServerError::Registry(err) => err.into(),
+      ServerError::Actor(err) => err.into(),
+      ServerError::Broadcast(err) => tonic::Status::aborted(err.to_string()),
+    }
+  }
+}
+
+impl From<RegistryError> for ServerError {
+  fn from(err: RegistryError) -> Self {
+    ServerError::Registry(err)
+  }
+}
+
+impl From<ActorError> for ServerError {
+  fn from(err: ActorError) -> Self {
+    ServerError::Actor(err)
+  }
+}
 
 #[derive(Debug)]
 pub(crate) struct WorkspaceServer {
   workspace_sender: WorkspaceSender,
   simulation_sender: SimulationSender,
   download_sender: DownloadSender,
-  // workspaces: Actor<events::WorkspaceEvent>,
-  the_workspace: msg::Workspace,
+  // Shared with the simulation actor, which reads the settings from it when it restarts
+  registry: Arc<RwLock<WorkspaceRegistry>>,
+  control_sessions: Arc<Mutex<ControlSessions>>,
+  latency: Mutex<LatencyTable>,
+  metrics: Arc<Metrics>,
 }
 
 impl WorkspaceServer {
@@ -26,61 +95,259 @@ impl WorkspaceServer {
     workspace_sender: WorkspaceSender,
     simulation_sender: SimulationSender,
     download_sender: DownloadSender,
+    registry: Arc<RwLock<WorkspaceRegistry>>,
+    control_sessions: Arc<Mutex<ControlSessions>>,
+    metrics: Arc<Metrics>,
   ) -> Self {
     Self {
       workspace_sender,
       simulation_sender,
       download_sender,
-      the_workspace: msg::Workspace {
-        name: "The Workspace".to_string(),
-        controller: "desktop".to_string(),
-        target: "".to_string(), // Why can't this be None?
-        devices: vec![
-          msg::Device {
-            name: "desktop".to_string(),
-            controller: true,
-            files: vec![msg::SharedFile {
-              relative_path: "simulate".into(),
-              size: None,
-            }],
-          },
-          msg::Device {
-            name: "laptop".to_string(),
-            controller: false,
-            files: vec![],
-          },
-        ],
-        monitors: vec![
-          msg::Monitor {
-            name: "left".to_string(),
-            x: 0,
-            y: 0,
-            w: 1920,
-            h: 1080,
-            device: "desktop".to_string(),
-          },
-          msg::Monitor {
-            name: "middle".to_string(),
-            x: 1920,
-            y: 0,
-            w: 1920,
-            h: 1200,
-            device: "desktop".to_string(),
-          },
-          msg::Monitor {
-            name: "right".to_string(),
-            x: 3840,
-            y: 0,
-            w: 1920,
-            h: 1080,
-            device: "desktop".to_string(),
-          },
-        ],
-      },
+      registry,
+      control_sessions,
+      latency: Mutex::new(LatencyTable::default()),
+      metrics,
+    }
+  }
+
+  fn lock_sessions(&self) -> Result<MutexGuard<'_, ControlSessions>, ServerError> {
+    lock_sessions(&self.control_sessions)
+  }
+
+  fn lock_latency(&self) -> Result<MutexGuard<'_, LatencyTable>, ServerError> {
+    self.latency.lock().map_err(|_| ServerError::Poisoned("Latency table"))
+  }
+
+  fn read_registry(&self) -> Result<RwLockReadGuard<'_, WorkspaceRegistry>, ServerError> {
+    self
+      .registry
+      .read()
+      .map_err(|_| ServerError::Poisoned("Workspace registry"))
+  }
+
+  fn write_registry(&self) -> Result<RwLockWriteGuard<'_, WorkspaceRegistry>, ServerError> {
+    self
+      .registry
+      .write()
+      .map_err(|_| ServerError::Poisoned("Workspace registry"))
+  }
+
+  fn broadcast_configuration(&self, workspace: &msg::Workspace) -> Result<(), ServerError> {
+    self
+      .workspace_sender
+      .send(SubscriptionEvent::WorkspaceEvent(
+        workspace.name.clone(),
+        msg::WorkspaceEvent {
+          event_type: Some(msg::workspace_event::EventType::ConfigurationUpdate(
+            msg::ConfigurationUpdate {
+              workspace: Some(workspace.clone()),
+            },
+          )),
+          trace_context: telemetry::current_context(),
+        },
+      ))
+      .map_err(ServerError::Broadcast)
+  }
+
+  // Drops every stream the device still has open in the workspace
+  fn close_device_sessions(&self, workspace_name: &ids::WorkspaceName, device_name: &ids::DeviceName) {
+    if let Ok(mut sessions) = self.lock_sessions() {
+      sessions.end_device(workspace_name, device_name);
+    }
+
+    if let Err(err) = self.simulation_sender.send(SimulationEvent::RemoveSimulator(
+      workspace_name.clone(),
+      device_name.clone(),
+    )) {
+      tracing::error!("Unable to remove simulator: {:?}", err);
+    }
+
+    if let Err(err) = self.download_sender.send(DownloadEvent::DeviceLeaving(
+      workspace_name.clone(),
+      device_name.clone(),
+    )) {
+      tracing::error!("Unable to cancel downloads: {:?}", err);
+    }
+
+    if let Err(err) = self.workspace_sender.send(SubscriptionEvent::Unsubscribe(
+      workspace_name.clone(),
+      device_name.clone(),
+    )) {
+      tracing::error!("Unable to remove subscription: {:?}", err);
+    }
+  }
+
+  // Records the lease holder in the workspace so subscribers see the new controller
+  fn update_controller(
+    &self,
+    workspace_name: &ids::WorkspaceName,
+    device_name: &ids::DeviceName,
+  ) -> Result<msg::Workspace, ServerError> {
+    let workspace = self.write_registry()?.set_controller(workspace_name, device_name)?;
+    self.broadcast_configuration(&workspace)?;
+    Ok(workspace)
+  }
+
+  fn apply_settings(
+    &self,
+    workspace_name: &ids::WorkspaceName,
+    settings: msg::WorkspaceSettings,
+  ) -> Result<(), ServerError> {
+    self
+      .simulation_sender
+      .send(SimulationEvent::ApplySettings(workspace_name.clone(), settings))?;
+    Ok(())
+  }
+
+  async fn live_status(&self, workspace_name: &ids::WorkspaceName) -> Result<LiveStatus, ServerError> {
+    let status = self
+      .workspace_sender
+      .request(|reply| SubscriptionEvent::Status(workspace_name.clone(), reply))
+      .await?;
+    Ok(status)
+  }
+
+  fn close_workspace_sessions(&self, workspace_name: &ids::WorkspaceName) {
+    if let Ok(mut sessions) = self.lock_sessions() {
+      sessions.end_workspace(workspace_name);
+    }
+    if let Ok(mut latency) = self.lock_latency() {
+      latency.remove_workspace(workspace_name);
+    }
+
+    if let Err(err) = self
+      .simulation_sender
+      .send(SimulationEvent::WorkspaceClosing(workspace_name.clone()))
+    {
+      tracing::error!("Unable to close simulators: {:?}", err);
+    }
+
+    if let Err(err) = self
+      .download_sender
+      .send(DownloadEvent::WorkspaceClosing(workspace_name.clone()))
+    {
+      tracing::error!("Unable to close downloads: {:?}", err);
+    }
+
+    if let Err(err) = self
+      .workspace_sender
+      .send(SubscriptionEvent::WorskpaceClosing(workspace_name.clone()))
+    {
+      tracing::error!("Unable to close subscriptions: {:?}", err);
+    }
+  }
+}
+
+// Moved into the response stream so the actor hears about a client that went away as soon as tonic drops the
+// stream, instead of on the next failed send. The sender is held weakly so the actor can still end the stream.
+struct ClosedStreamGuard<S: StreamSender, E> {
+  workspace_name: ids::WorkspaceName,
+  device_name: ids::DeviceName,
+  stream_sender: S::Weak,
+  actor_sender: ActorHandle<E>,
+  closed_event: fn(ids::WorkspaceName, ids::DeviceName, S) -> E,
+}
+
+impl<S: StreamSender, E> ClosedStreamGuard<S, E> {
+  fn new(
+    workspace_name: ids::WorkspaceName,
+    device_name: ids::DeviceName,
+    stream_sender: &S,
+    actor_sender: ActorHandle<E>,
+    closed_event: fn(ids::WorkspaceName, ids::DeviceName, S) -> E,
+  ) -> Self {
+    Self {
+      workspace_name,
+      device_name,
+      stream_sender: stream_sender.downgrade(),
+      actor_sender,
+      closed_event,
+    }
+  }
+}
+
+impl<S: StreamSender, E> Drop for ClosedStreamGuard<S, E> {
+  fn drop(&mut self) {
+    // Nothing to report when the actor already let go of the stream
+    if let Some(stream_sender) = S::upgrade(&self.stream_sender) {
+      let event = (self.closed_event)(self.workspace_name.clone(), self.device_name.clone(), stream_sender);
+      if self.actor_sender.send(event).is_err() {
+        tracing::error!(
+          workspace = %self.workspace_name,
+          device = %self.device_name,
+          "Unable to report the closed stream"
+        );
+      }
+    }
+  }
+}
+
+fn lock_sessions(sessions: &Mutex<ControlSessions>) -> Result<MutexGuard<'_, ControlSessions>, ServerError> {
+  sessions.lock().map_err(|_| ServerError::Poisoned("Control sessions"))
+}
+
+// Detaches the control stream from the lease when the handler finishes or is dropped
+struct ControlStreamGuard {
+  sessions: Arc<Mutex<ControlSessions>>,
+  workspace_name: ids::WorkspaceName,
+  id: u64,
+}
+
+impl Drop for ControlStreamGuard {
+  fn drop(&mut self) {
+    if let Ok(mut sessions) = lock_sessions(&self.sessions) {
+      sessions.detach(&self.workspace_name, self.id);
+    }
+  }
+}
+
+// Announces a device when created and again when dropped, so a stream handler
+// that is cancelled because the client went away still reports the disconnect
+struct PresenceGuard {
+  workspace_sender: WorkspaceSender,
+  workspace_name: ids::WorkspaceName,
+  device_name: ids::DeviceName,
+  role: msg::DeviceRole,
+}
+
+impl PresenceGuard {
+  fn new(
+    workspace_sender: WorkspaceSender,
+    workspace_name: ids::WorkspaceName,
+    device_name: ids::DeviceName,
+    role: msg::DeviceRole,
+  ) -> Self {
+    let guard = Self {
+      workspace_sender,
+      workspace_name,
+      device_name,
+      role,
+    };
+    guard.announce(true);
+    guard
+  }
+
+  fn announce(&self, connected: bool) {
+    if let Err(err) = self.workspace_sender.send(SubscriptionEvent::WorkspaceEvent(
+      self.workspace_name.clone(),
+      presence_event(&self.device_name, self.role, connected),
+    )) {
+      tracing::error!(
+        workspace = %self.workspace_name,
+        device = %self.device_name,
+        "Unable to announce the device: {:?}",
+        err
+      );
     }
   }
 }
 
+impl Drop for PresenceGuard {
+  fn drop(&mut self) {
+    self.announce(false);
+  }
+}
+
 // fn map_transition(event: events::WorkspaceSubscriptionEvent) -> Result<msg::WorkspaceEvent, Status> {
 //   match event {
 //     events::WorkspaceSubscriptionEvent::SetTarget(_, _) => Ok(msg::WorkspaceEvent {
@@ -104,89 +371,213 @@ impl VirtualWorkspaces for WorkspaceServer {
   type UploadFileStream =
     Pin<Box<dyn futures_core::Stream<Item = std::result::Result<msg::UploadResponse, tonic::Status>> + Send + 'static>>;
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace))]
   async fn create_workspace(
     &self,
-    _request: tonic::Request<msg::CreateRequest>,
+    request: tonic::Request<msg::CreateRequest>,
   ) -> std::result::Result<tonic::Response<msg::CreatedResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let workspace_name = request.into_inner().workspace;
     tracing::info!("Create workspace request");
-    Err(tonic::Status::internal("Not implemented"))
+    self.write_registry()?.create(workspace_name.clone())?;
+    // A workspace that was deleted and created again starts over with the default settings
+    self.apply_settings(&workspace_name, msg::WorkspaceSettings::default())?;
+    Ok(tonic::Response::new(msg::CreatedResponse {}))
   }
 
+  #[tracing::instrument(skip_all)]
   async fn list_workspaces(
     &self,
-    _request: tonic::Request<msg::ListRequest>,
+    request: tonic::Request<msg::ListRequest>,
   ) -> std::result::Result<tonic::Response<msg::WorkspaceList>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     tracing::info!("Listing workspaces");
-    Err(tonic::Status::internal("Not implemented"))
+    Ok(tonic::Response::new(msg::WorkspaceList {
+      workspaces: self.read_registry()?.summaries(),
+    }))
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().name))]
   async fn get_workspace(
     &self,
     request: tonic::Request<msg::GetRequest>,
   ) -> std::result::Result<tonic::Response<msg::Workspace>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     let request = request.into_inner();
-    tracing::info!("Getting workspace {}", request.name);
-    Ok(tonic::Response::new(self.the_workspace.clone()))
+    tracing::info!("Getting workspace");
+    let mut workspace = match self.read_registry()?.get(&request.name) {
+      Some(workspace) => workspace.clone(),
+      None => return Err(tonic::Status::not_found(format!("No workspace named {}", request.name))),
+    };
+    workspace.target = self.live_status(&request.name).await?.target.unwrap_or_default();
+    Ok(tonic::Response::new(workspace))
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace))]
+  async fn get_workspace_status(
+    &self,
+    request: tonic::Request<msg::StatusRequest>,
+  ) -> std::result::Result<tonic::Response<msg::WorkspaceStatus>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let request = request.into_inner();
+    tracing::info!("Getting the status of the workspace");
+    let mut workspace = match self.read_registry()?.get(&request.workspace) {
+      Some(workspace) => workspace.clone(),
+      None => {
+        return Err(tonic::Status::not_found(format!(
+          "No workspace named {}",
+          request.workspace
+        )))
+      }
+    };
+    let status = self.live_status(&request.workspace).await?;
+    workspace.target = status.target.unwrap_or_default();
+    Ok(tonic::Response::new(msg::WorkspaceStatus {
+      workspace: Some(workspace),
+      sessions: status.sessions,
+    }))
+  }
+
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace))]
   async fn configure_workspace(
     &self,
-    _request: tonic::Request<msg::ConfigurationRequest>,
+    request: tonic::Request<msg::ConfigurationRequest>,
   ) -> std::result::Result<tonic::Response<msg::ConfiguredResponse>, tonic::Status> {
-    tracing::info!("Configuring workspace {}", "implement me");
-    Err(tonic::Status::internal("Not implemented"))
+    telemetry::continue_trace(request.metadata());
+    let request = request.into_inner();
+    tracing::info!("Configuring workspace");
+    let controller = request.controller.clone();
+    let settings_changed = request.settings.is_some();
+    let removed_devices = request.removed_devices.clone();
+    let workspace = self.write_registry()?.configure(request)?;
+    // A device removed and added again in the same request keeps its streams
+    for device_name in removed_devices
+      .iter()
+      .filter(|device_name| !workspace.devices.iter().any(|device| &device.name == *device_name))
+    {
+      self.close_device_sessions(&workspace.name, device_name);
+    }
+    if settings_changed {
+      self.apply_settings(&workspace.name, workspace.settings.clone().unwrap_or_default())?;
+    }
+    // Configuring the controller moves the lease as if it was forced over
+    match controller {
+      Some(controller) if controller.is_empty() => self.lock_sessions()?.end_workspace(&workspace.name),
+      Some(controller) => {
+        self.lock_sessions()?.handover(&workspace.name, &controller, true)?;
+      }
+      None => {}
+    }
+    self.broadcast_configuration(&workspace)?;
+    Ok(tonic::Response::new(msg::ConfiguredResponse {
+      workspace: Some(workspace),
+    }))
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace))]
   async fn delete_workspace(
     &self,
-    _request: tonic::Request<msg::DeleteRequest>,
+    request: tonic::Request<msg::DeleteRequest>,
   ) -> std::result::Result<tonic::Response<msg::DeleteResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let workspace_name = request.into_inner().workspace;
     tracing::info!("Delete workspace request");
-    Err(tonic::Status::internal("Not implemented"))
+    self.write_registry()?.remove(&workspace_name)?;
+    self.close_workspace_sessions(&workspace_name);
+    self.metrics.remove_workspace(&workspace_name);
+    Ok(tonic::Response::new(msg::DeleteResponse {}))
+  }
+
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
+  async fn join_workspace(
+    &self,
+    request: tonic::Request<msg::JoinRequest>,
+  ) -> std::result::Result<tonic::Response<msg::JoinResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let request = request.into_inner();
+    tracing::info!("Joining with {} monitors", request.monitors.len());
+    let workspace = self.write_registry()?.join(request)?;
+    self.broadcast_configuration(&workspace)?;
+    Ok(tonic::Response::new(msg::JoinResponse {
+      workspace: Some(workspace),
+    }))
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
+  async fn leave_workspace(
+    &self,
+    request: tonic::Request<msg::LeaveRequest>,
+  ) -> std::result::Result<tonic::Response<msg::LeaveResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let request = request.into_inner();
+    tracing::info!("Leaving the workspace");
+    let device_name = request.device.clone();
+    let workspace = self.write_registry()?.leave(request)?;
+    self.close_device_sessions(&workspace.name, &device_name);
+    self.broadcast_configuration(&workspace)?;
+    Ok(tonic::Response::new(msg::LeaveResponse {
+      workspace: Some(workspace),
+    }))
+  }
+
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
   async fn target_device(
     &self,
     request: tonic::Request<msg::TargetRequest>,
   ) -> std::result::Result<tonic::Response<msg::TargetResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     let request = request.into_inner();
     let workspace_name = request.workspace;
     let device_name = request.device;
     let clipboard = request.clipboard;
-    tracing::info!("Workspace {} will now target {}", workspace_name, device_name);
-    if let Err(err) = self.simulation_sender.send(SimulationEvent::TargetEvent(
-      workspace_name.clone(),
-      device_name.clone(),
-    )) {
-      println!("Failed to send listener removed event: {:?}", err);
-      return Err(tonic::Status::from_error(Box::new(err)));
+    let controller = self.read_registry()?.device(&workspace_name, &device_name)?.controller;
+
+    // Devices without a simulation stream can only be targetted when they use their own input
+    let simulating = self
+      .simulation_sender
+      .request(|reply| SimulationEvent::IsSimulating(workspace_name.clone(), device_name.clone(), reply))
+      .await?;
+    if !simulating && !controller {
+      return Err(TargetError::NotSimulating(workspace_name, device_name).into());
+    }
+
+    if request.take_control {
+      self.read_registry()?.check_controller(&workspace_name, &device_name)?;
+    }
+    tracing::info!("Targetting the device");
+
+    let previous = self
+      .workspace_sender
+      .request(|reply| SubscriptionEvent::TargetEvent(workspace_name.clone(), device_name.clone(), clipboard, reply))
+      .await??;
+
+    // Control only moves once the device is the target, a refused target leaves the lease where it was
+    if request.take_control {
+      self.lock_sessions()?.handover(&workspace_name, &device_name, true)?;
+      self.update_controller(&workspace_name, &device_name)?;
     }
 
-    if let Err(err) = self.workspace_sender.send(SubscriptionEvent::TargetEvent(
+    if let Err(err) = self.simulation_sender.send(SimulationEvent::TargetEvent(
       workspace_name.clone(),
       device_name.clone(),
-      clipboard,
-    ))
-    // SubscriptionEvent::WorkspaceEvent(
-    // workspace_name,
-    // msg::WorkspaceEvent {
-    //   event_type: Some(msg::workspace_event::EventType::TargetUpdate(msg::TargetUpdate {
-    //     device: device_name,
-    //   })),
-    // },
-    // ))
-    {
-      println!("Failed to notify listeners of target update event: {:?}", err);
+    )) {
+      tracing::error!("Failed to send listener removed event: {:?}", err);
       return Err(tonic::Status::from_error(Box::new(err)));
     }
 
-    return Ok(tonic::Response::new(msg::TargetResponse {}));
+    return Ok(tonic::Response::new(msg::TargetResponse {
+      changed: previous.as_ref() != Some(&device_name),
+      previous: previous.unwrap_or_default(),
+      current: device_name,
+    }));
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
   async fn cancel_simulation(
     &self,
     request: tonic::Request<msg::CancelSimulationRequest>,
   ) -> std::result::Result<tonic::Response<msg::CancelSimulationResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     tracing::info!("Cancel simulation request");
     let request = request.into_inner();
     let workspace_name = request.workspace;
@@ -195,16 +586,18 @@ impl VirtualWorkspaces for WorkspaceServer {
       .simulation_sender
       .send(SimulationEvent::RemoveSimulator(workspace_name, device_name))
     {
-      println!("Failed to send listener removed event: {:?}", err);
+      tracing::error!("Failed to send listener removed event: {:?}", err);
       return Err(tonic::Status::from_error(Box::new(err)));
     }
     return Ok(tonic::Response::new(msg::CancelSimulationResponse {}));
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
   async fn cancel_subscription(
     &self,
     request: tonic::Request<msg::CancelSubscriptionRequest>,
   ) -> std::result::Result<tonic::Response<msg::CancelSubscriptionResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     tracing::info!("Cancel subscription request");
     let request = request.into_inner();
     let workspace_name = request.workspace;
@@ -213,43 +606,162 @@ impl VirtualWorkspaces for WorkspaceServer {
       .workspace_sender
       .send(SubscriptionEvent::Unsubscribe(workspace_name, device_name))
     {
-      println!("Failed to send listener removed event: {:?}", err);
+      tracing::error!("Failed to send listener removed event: {:?}", err);
       return Err(tonic::Status::from_error(Box::new(err)));
     }
     return Ok(tonic::Response::new(msg::CancelSubscriptionResponse {}));
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
+  async fn heartbeat(
+    &self,
+    request: tonic::Request<msg::HeartbeatRequest>,
+  ) -> std::result::Result<tonic::Response<msg::HeartbeatResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let request = request.into_inner();
+    tracing::debug!("Heartbeat");
+    self
+      .workspace_sender
+      .send(SubscriptionEvent::DeviceHeartbeat(
+        request.workspace.clone(),
+        request.device.clone(),
+      ))
+      .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+    self
+      .simulation_sender
+      .send(SimulationEvent::DeviceHeartbeat(request.workspace, request.device))
+      .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+    Ok(tonic::Response::new(msg::HeartbeatResponse {
+      server_time_us: now_us(),
+    }))
+  }
+
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
+  async fn handover_control(
+    &self,
+    request: tonic::Request<msg::HandoverRequest>,
+  ) -> std::result::Result<tonic::Response<msg::HandoverResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let request = request.into_inner();
+    tracing::info!("{} control", if request.force { "Taking" } else { "Requesting" });
+    self
+      .read_registry()?
+      .check_controller(&request.workspace, &request.device)?;
+    let previous = self
+      .lock_sessions()?
+      .handover(&request.workspace, &request.device, request.force)?;
+    let workspace = self.update_controller(&request.workspace, &request.device)?;
+    Ok(tonic::Response::new(msg::HandoverResponse {
+      previous: previous.unwrap_or_default(),
+      workspace: Some(workspace),
+    }))
+  }
+
+  #[tracing::instrument(skip_all, fields(workspace, device))]
   async fn control_workspace(
     &self,
     request: tonic::Request<tonic::Streaming<msg::ControlRequest>>,
   ) -> std::result::Result<tonic::Response<msg::ControlResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     let mut stream = request.into_inner();
 
     if let Some(Ok(msg::ControlRequest {
       event_type: Some(msg::control_request::EventType::Workspace(msg::ControlWorkspace { workspace, device })),
     })) = stream.next().await
     {
-      println!("Device {} will control workspace {}", device, workspace);
-      while let Some(req) = stream.next().await {
+      let span = tracing::Span::current();
+      span.record("workspace", workspace.as_str());
+      span.record("device", device.as_str());
+      self.read_registry()?.check_controller(&workspace, &device)?;
+      let attached = self.lock_sessions()?.attach(&workspace, &device)?;
+      let _stream_guard = ControlStreamGuard {
+        sessions: self.control_sessions.clone(),
+        workspace_name: workspace.clone(),
+        id: attached.id,
+      };
+      if attached.granted {
+        self.update_controller(&workspace, &device)?;
+      }
+      let mut revoked = attached.revoked;
+
+      tracing::info!("Controlling the workspace");
+      let _presence = PresenceGuard::new(
+        self.workspace_sender.clone(),
+        workspace.clone(),
+        device,
+        msg::DeviceRole::Controller,
+      );
+      let input_events = self.metrics.input_events.with_label_values(&[&workspace]);
+      // Input waits in a queue while the simulation actor is busy, under the policy the workspace had when
+      // the stream was opened. The actor is sent one input at a time so its mailbox never grows with it.
+      let policy = OverflowPolicy::new(
+        &self
+          .read_registry()?
+          .get(&workspace)
+          .and_then(|workspace| workspace.settings.clone())
+          .unwrap_or_default(),
+      );
+      let (input_sender, mut input_receiver) = input_queue();
+      let simulation_sender = self.simulation_sender.clone();
+      let relay_workspace = workspace.clone();
+      tokio::task::spawn(
+        async move {
+          while let Some(event) = input_receiver.recv().await {
+            if let Err(err) = simulation_sender
+              .request(|relayed| SimulationEvent::SimulationEvent(relay_workspace.clone(), event, relayed))
+              .await
+            {
+              // Dropping the receiver ends the control stream
+              tracing::error!("Unable to relay input: {}", err);
+              break;
+            }
+          }
+        }
+        .in_current_span(),
+      );
+      loop {
+        let req = tokio::select! {
+          req = stream.next() => req,
+          reason = &mut revoked => {
+            if matches!(reason, Ok(Revoked::ShuttingDown)) {
+              return Err(tonic::Status::unavailable("The server is shutting down"));
+            }
+            return Err(tonic::Status::aborted("Control of the workspace was handed over"));
+          }
+        };
+        let Some(req) = req else {
+          break;
+        };
         if let Ok(msg::ControlRequest {
           event_type: Some(msg::control_request::EventType::InputEvent(input_event)),
         }) = req
         {
-          self
-            .simulation_sender
-            .send(SimulationEvent::SimulationEvent(
-              self.the_workspace.name.clone(),
-              msg::SimulationEvent {
-                input_event: Some(input_event.clone()),
-              },
-            ))
-            .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+          let event = msg::SimulationEvent {
+            input_event: Some(input_event),
+            heartbeat: None,
+            relayed_at_us: now_us(),
+          };
+          match input_sender.send(event, &policy) {
+            Ok(()) => input_events.inc(),
+            Err(QueueError::Closed) => return Err(tonic::Status::aborted("Input is no longer relayed")),
+            Err(QueueError::Overflowed(duration)) => {
+              tracing::warn!(
+                "Input has not been relayed for {:?}, closing the control stream",
+                duration
+              );
+              self.metrics.dropped("control");
+              return Err(tonic::Status::resource_exhausted(format!(
+                "Input has not been relayed for {:?}",
+                duration
+              )));
+            }
+          }
         } else {
-          tracing::info!("Invalid control message");
+          tracing::warn!("Invalid control message");
           return Err(tonic::Status::aborted("Invalid control message"));
         }
       }
-      println!("Done with control workspace request");
+      tracing::info!("Done with control workspace request");
       Ok(tonic::Response::new(msg::ControlResponse {}))
     } else {
       return Err(tonic::Status::aborted("No messages in control stream"));
@@ -259,56 +771,86 @@ impl VirtualWorkspaces for WorkspaceServer {
     }
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
   async fn simulate_workspace(
     &self,
     request: tonic::Request<msg::SimulateRequest>,
   ) -> std::result::Result<tonic::Response<Self::SimulateWorkspaceStream>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     let request = request.into_inner();
     let workspace_name = request.workspace;
     let device_name = request.device;
-    let (sender, receiver) = mpsc::unbounded_channel::<msg::SimulationEvent>();
+    let (sender, receiver) = input_queue();
 
-    println!("Adding device {} as a simulator for {}.", device_name, workspace_name);
+    tracing::info!("Adding the device as a simulator");
 
-    if let Err(err) = self
-      .simulation_sender
-      .send(SimulationEvent::AddSimulator(workspace_name, device_name, sender))
-    {
+    if let Err(err) = self.simulation_sender.send(SimulationEvent::AddSimulator(
+      workspace_name.clone(),
+      device_name.clone(),
+      sender.clone(),
+    )) {
       return Err(tonic::Status::from_error(Box::new(err)));
     }
+    let guard = ClosedStreamGuard::new(
+      workspace_name,
+      device_name,
+      &sender,
+      self.simulation_sender.clone(),
+      SimulationEvent::SimulatorClosed,
+    );
 
-    let response_stream = tokio_stream::wrappers::UnboundedReceiverStream::new(receiver).map(Ok::<_, tonic::Status>);
+    // The guard lives as long as the stream
+    let response_stream = futures::stream::unfold((receiver, guard), |(mut receiver, guard)| async move {
+      receiver.recv().await.map(|event| (event, (receiver, guard)))
+    })
+    .map(Ok::<_, tonic::Status>);
     Ok(tonic::Response::new(Box::pin(response_stream)))
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
   async fn subscribe_to_workspace(
     &self,
     request: tonic::Request<msg::WorkspaceSubscriptionRequest>,
   ) -> std::result::Result<tonic::Response<Self::SubscribeToWorkspaceStream>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     let request = request.into_inner();
     let workspace_name = request.workspace;
     let device_name = request.device;
     let (sender, receiver) = mpsc::unbounded_channel::<msg::WorkspaceEvent>();
 
-    println!("Adding device {} as a listener for {}.", device_name, workspace_name);
+    tracing::info!("Adding the device as a listener");
 
-    if let Err(err) = self
-      .workspace_sender
-      .send(SubscriptionEvent::Subscribe(workspace_name, device_name, sender))
-    {
+    if let Err(err) = self.workspace_sender.send(SubscriptionEvent::Subscribe(
+      workspace_name.clone(),
+      device_name.clone(),
+      sender.clone(),
+    )) {
       return Err(tonic::Status::from_error(Box::new(err)));
     }
+    let guard = ClosedStreamGuard::new(
+      workspace_name,
+      device_name,
+      &sender,
+      self.workspace_sender.clone(),
+      SubscriptionEvent::SubscriptionClosed,
+    );
 
-    let response_stream = tokio_stream::wrappers::UnboundedReceiverStream::new(receiver).map(Ok::<_, tonic::Status>);
+    // The guard lives as long as the stream
+    let response_stream = futures::stream::unfold((receiver, guard), |(mut receiver, guard)| async move {
+      receiver.recv().await.map(|event| (event, (receiver, guard)))
+    })
+    .map(Ok::<_, tonic::Status>);
     Ok(tonic::Response::new(Box::pin(response_stream)))
   }
 
+  #[tracing::instrument(skip_all, fields(workspace, downloader, uploader, path))]
   async fn download_file(
     &self,
     request: tonic::Request<tonic::Streaming<msg::DownloadRequest>>,
   ) -> std::result::Result<tonic::Response<Self::DownloadFileStream>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     let mut stream = request.into_inner();
-    println!("Download file request");
+    tracing::info!("Download file request");
     if let Some(Ok(msg::DownloadRequest {
       r#type: Some(msg::download_request::Type::Initiate(initiate_request)),
     })) = stream.next().await
@@ -316,7 +858,8 @@ impl VirtualWorkspaces for WorkspaceServer {
       let (sender, receiver) = mpsc::unbounded_channel::<msg::DownloadResponse>();
       let download_key = DownloadKey::new2(&initiate_request);
 
-      println!("Initiating download for {:?}", download_key);
+      download_key.record(&tracing::Span::current());
+      tracing::info!("Initiating download");
 
       if let Err(err) = self
         .download_sender
@@ -325,7 +868,7 @@ impl VirtualWorkspaces for WorkspaceServer {
         return Err(tonic::Status::from_error(Box::new(err)));
       }
 
-      println!("Sending download requested to workspace manager");
+      tracing::debug!("Sending download requested to workspace manager");
 
       if let Err(err) = self.workspace_sender.send(SubscriptionEvent::DownloadRequested(
         initiate_request.workspace.clone(),
@@ -335,43 +878,44 @@ impl VirtualWorkspaces for WorkspaceServer {
         return Err(tonic::Status::from_error(Box::new(err)));
       }
 
-      println!("download: handling other messages");
-
       let download_sender = self.download_sender.clone();
-      tokio::task::spawn(async move {
-        while let Some(req) = stream.next().await {
-          if let Ok(msg::DownloadRequest {
-            r#type: Some(download_request),
-          }) = req
-          {
-            match download_request {
-              msg::download_request::Type::Initiate(_) => {
-                eprintln!("Initiate message should only be sent once");
-              }
-              msg::download_request::Type::Request(chunk_request) => {
-                println!(
-                  "Sending download chunk request to download manager {}",
-                  chunk_request.offset
-                );
-                if let Err(err) = download_sender.send(DownloadEvent::RequestFileChunk(
-                  download_key.clone(),
-                  chunk_request.offset,
-                )) {
-                  println!("Failed to send download data to download manager: {:?}", err);
+      tokio::task::spawn(
+        async move {
+          while let Some(req) = stream.next().await {
+            if let Ok(msg::DownloadRequest {
+              r#type: Some(download_request),
+            }) = req
+            {
+              match download_request {
+                msg::download_request::Type::Initiate(_) => {
+                  tracing::warn!("Initiate message should only be sent once");
                 }
-              }
-              msg::download_request::Type::Complete(_) => {
-                println!("Sending download complete to download manager");
-                if let Err(err) = download_sender.send(DownloadEvent::DownloadComplete(download_key.clone())) {
-                  println!("Failed to send download complete to download manager: {:?}", err);
+                msg::download_request::Type::Request(chunk_request) => {
+                  tracing::trace!(
+                    offset = chunk_request.offset,
+                    "Sending download chunk request to download manager"
+                  );
+                  if let Err(err) = download_sender.send(DownloadEvent::RequestFileChunk(
+                    download_key.clone(),
+                    chunk_request.offset,
+                  )) {
+                    tracing::error!("Failed to send download data to download manager: {:?}", err);
+                  }
+                }
+                msg::download_request::Type::Complete(_) => {
+                  tracing::info!("Sending download complete to download manager");
+                  if let Err(err) = download_sender.send(DownloadEvent::DownloadComplete(download_key.clone())) {
+                    tracing::error!("Failed to send download complete to download manager: {:?}", err);
+                  }
                 }
               }
+            } else {
+              tracing::warn!("Invalid download message");
             }
-          } else {
-            println!("Invalid download message");
           }
         }
-      });
+        .instrument(tracing::Span::current()),
+      );
 
       let response_stream = tokio_stream::wrappers::UnboundedReceiverStream::new(receiver).map(Ok::<_, tonic::Status>);
       Ok(tonic::Response::new(Box::pin(response_stream)))
@@ -380,21 +924,22 @@ impl VirtualWorkspaces for WorkspaceServer {
     }
   }
 
+  #[tracing::instrument(skip_all, fields(workspace, downloader, uploader, path))]
   async fn upload_file(
     &self,
     request: tonic::Request<tonic::Streaming<msg::UploadRequest>>,
   ) -> std::result::Result<tonic::Response<Self::UploadFileStream>, tonic::Status> {
-    println!("Upload file request");
+    telemetry::continue_trace(request.metadata());
+    tracing::info!("Upload file request");
     let mut stream = request.into_inner();
     if let Some(Ok(msg::UploadRequest {
       r#type: Some(msg::upload_request::Type::Initiate(initiate_request)),
     })) = stream.next().await
     {
-      println!("Initiating upload for {:?}", initiate_request);
       let (sender, receiver) = mpsc::unbounded_channel::<msg::UploadResponse>();
-      println!("Creating download key");
       let download_key = DownloadKey::new(&initiate_request);
-      println!("Download key: {:?}", download_key);
+      download_key.record(&tracing::Span::current());
+      tracing::info!(chunks = initiate_request.number_of_chunks, "Initiating upload");
       if let Err(err) = self.download_sender.send(DownloadEvent::ConnectUploader(
         download_key.clone(),
         sender,
@@ -402,33 +947,34 @@ impl VirtualWorkspaces for WorkspaceServer {
       )) {
         return Err(tonic::Status::from_error(Box::new(err)));
       }
-      println!("Sending upload requested to workspace manager");
 
       let download_sender = self.download_sender.clone();
-      tokio::task::spawn(async move {
-        while let Some(req) = stream.next().await {
-          if let Ok(msg::UploadRequest {
-            r#type: Some(upload_request),
-          }) = req
-          {
-            match upload_request {
-              msg::upload_request::Type::Initiate(_) => {
-                eprintln!("Initiate message should only be sent once");
-              }
-              msg::upload_request::Type::Chunk(chunk) => {
-                println!("Received chunk {:?}", chunk.offset);
-                if let Err(err) = download_sender.send(DownloadEvent::SendFileChunk(download_key.clone(), chunk)) {
-                  println!("Failed to send upload chunk request to download manager: {:?}", err);
+      tokio::task::spawn(
+        async move {
+          while let Some(req) = stream.next().await {
+            if let Ok(msg::UploadRequest {
+              r#type: Some(upload_request),
+            }) = req
+            {
+              match upload_request {
+                msg::upload_request::Type::Initiate(_) => {
+                  tracing::warn!("Initiate message should only be sent once");
+                }
+                msg::upload_request::Type::Chunk(chunk) => {
+                  tracing::trace!(offset = chunk.offset, "Received chunk");
+                  if let Err(err) = download_sender.send(DownloadEvent::SendFileChunk(download_key.clone(), chunk)) {
+                    tracing::error!("Failed to send upload chunk request to download manager: {:?}", err);
+                  }
                 }
               }
+            } else {
+              tracing::warn!("Invalid upload message");
             }
-          } else {
-            println!("Invalid upload message");
           }
         }
-      });
+        .instrument(tracing::Span::current()),
+      );
 
-      println!("upload: returning response stream");
       let response_stream = tokio_stream::wrappers::UnboundedReceiverStream::new(receiver).map(Ok::<_, tonic::Status>);
       Ok(tonic::Response::new(Box::pin(response_stream)))
     } else {
@@ -436,41 +982,70 @@ impl VirtualWorkspaces for WorkspaceServer {
     }
   }
 
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
+  async fn report_latency(
+    &self,
+    request: tonic::Request<msg::LatencyReport>,
+  ) -> std::result::Result<tonic::Response<msg::LatencyReportResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let report = request.into_inner();
+    self.read_registry()?.device(&report.workspace, &report.device)?;
+    tracing::debug!("Latency report");
+    self.lock_latency()?.record(report);
+    Ok(tonic::Response::new(msg::LatencyReportResponse {}))
+  }
+
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace))]
+  async fn get_latency_stats(
+    &self,
+    request: tonic::Request<msg::LatencyStatsRequest>,
+  ) -> std::result::Result<tonic::Response<msg::LatencyStats>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let request = request.into_inner();
+    if self.read_registry()?.get(&request.workspace).is_none() {
+      return Err(tonic::Status::not_found(format!(
+        "No workspace named {}",
+        request.workspace
+      )));
+    }
+    Ok(tonic::Response::new(self.lock_latency()?.stats(&request.workspace)))
+  }
+
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
   async fn share_file(
     &self,
     request: tonic::Request<msg::ShareFileRequest>,
   ) -> std::result::Result<tonic::Response<msg::ShareFileResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let request = request.into_inner();
+    tracing::info!(path = %request.relative_path, "Sharing a file");
+    let workspace = self.write_registry()?.share_file(request)?;
+    self.broadcast_configuration(&workspace)?;
     return Ok(tonic::Response::new(msg::ShareFileResponse {}));
   }
+
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace, device = %request.get_ref().device))]
   async fn remove_shared_file(
     &self,
     request: tonic::Request<msg::RemoveSharedFileRequest>,
   ) -> std::result::Result<tonic::Response<msg::RemoveSharedFileResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
+    let request = request.into_inner();
+    tracing::info!(path = %request.relative_path, "Stopped sharing a file");
+    let workspace = self.write_registry()?.remove_shared_file(request)?;
+    self.broadcast_configuration(&workspace)?;
     return Ok(tonic::Response::new(msg::RemoveSharedFileResponse {}));
   }
+
+  #[tracing::instrument(skip_all, fields(workspace = %request.get_ref().workspace))]
   async fn close_workspace(
     &self,
     request: tonic::Request<msg::CloseRequest>,
   ) -> std::result::Result<tonic::Response<msg::CloseResponse>, tonic::Status> {
+    telemetry::continue_trace(request.metadata());
     let workspace_name = request.into_inner().workspace;
-
-    if let Err(err) = self.simulation_sender
-      .send(SimulationEvent::WorkspaceClosing(workspace_name.clone()))
-    {
-      eprintln!("Unable to close simulators: {:?}", err);
-    }
-
-    if let Err(err) = self.download_sender
-      .send(DownloadEvent::WorkspaceClosing(workspace_name.clone())) {
-      eprintln!("Unable to close downloads: {:?}", err);
-    }
-
-    if let Err(err) = self.workspace_sender
-      .send(SubscriptionEvent::WorskpaceClosing(workspace_name.clone()))
-      .map_err(|e| tonic::Status::aborted(e.to_string())) {
-      eprintln!("Unable to close subscriptions: {:?}", err);
-    }
-
+    tracing::info!("Closing the workspace");
+    self.close_workspace_sessions(&workspace_name);
     return Ok(tonic::Response::new(msg::CloseResponse {}));
   }
 }
@@ -1,7 +1,3 @@
-use sinnergasm::grpc_client::GrpcClient;
-use sinnergasm::options::Options;
-use sinnergasm::protos as msg;
-use std::sync::Arc;
 use tokio::sync::broadcast::Sender;
 use ui_common::errors::RDevError;
 use ui_common::events;
@@ -18,21 +14,3 @@ pub(crate) fn listen_to_system(sender: Sender<events::AppEvent>) -> Result<(), R
   })?;
   Ok(())
 }
-
-pub(crate) async fn listen_to_client(
-  options: Arc<Options>,
-  mut client: GrpcClient,
-  sender: Sender<events::AppEvent>,
-) -> Result<(), anyhow::Error> {
-  let request = msg::SimulateRequest {
-    workspace: options.workspace.clone(),
-    device: options.device.clone(),
-  };
-  let mut stream = client.simulate_workspace(request).await?.into_inner();
-  while let Some(event) = stream.message().await? {
-    sender.send(events::AppEvent::SimulationEvent(
-      events::SimulationEvent::SimulateEvent(event),
-    ))?;
-  }
-  Ok(())
-}
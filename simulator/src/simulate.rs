@@ -1,18 +1,27 @@
 // pub mod display;
 pub mod events;
-pub mod handler;
 pub mod listener;
 
 use ui_common::subscribe::launch_subscription_task;
 
 use anyhow;
+use clap::Parser;
 use sinnergasm::grpc_client::create_client;
+use sinnergasm::logging::init_logging;
+use sinnergasm::options::ClientArgs;
 use sinnergasm::options::Options;
+use sinnergasm::telemetry;
+use ui_common::clock::ServerClock;
 use ui_common::device_display::display_devices;
+use ui_common::heartbeat::launch_heartbeat_task;
+use ui_common::latency::launch_latency_reporter;
+use ui_common::latency::LatencyRecorder;
+use ui_common::membership::join_workspace;
+use ui_common::membership::leave_workspace;
+use ui_common::simulation::listen_to_client;
+use ui_common::simulation::simulate_receiver;
 use ui_common::target::launch_send_targets_task;
 
-use crate::handler::simulate_receiver;
-use crate::listener::listen_to_client;
 use crate::listener::listen_to_system;
 use std::sync::Arc;
 use tokio::sync::broadcast;
@@ -22,14 +31,16 @@ fn die_early() {
 }
 
 fn print_type_of<T>(_: &T) {
-  println!("{}", std::any::type_name::<T>());
+  tracing::debug!("{}", std::any::type_name::<T>());
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 10)]
 async fn main() -> anyhow::Result<()> {
-  let options = Arc::new(Options::new("laptop".into()));
-  let client = create_client(&options).await?;
+  let options = Arc::new(Options::load(&ClientArgs::parse(), "laptop")?);
+  init_logging("simulator", options.trace_export.as_ref())?;
+  let mut client = create_client(&options).await?;
   print_type_of(&client);
+  join_workspace(&mut client, &options, false).await?;
 
   let (sender, _) = broadcast::channel(options.capacity);
 
@@ -37,6 +48,10 @@ async fn main() -> anyhow::Result<()> {
   let _ = std::thread::spawn(move || listen_to_system(sender_clone));
 
   let subscribe_task = launch_subscription_task(options.clone(), client.clone(), sender.clone(), false).await;
+  let clock = ServerClock::default();
+  let heartbeat_task = launch_heartbeat_task(options.clone(), client.clone(), clock.clone()).await;
+  let latency = LatencyRecorder::new(&options);
+  let latency_task = launch_latency_reporter(client.clone(), latency.clone()).await;
 
   let sender_clone = sender.clone();
   let client_clone = client.clone();
@@ -50,16 +65,27 @@ async fn main() -> anyhow::Result<()> {
 
   let receiver = sender.subscribe();
   let simulate_task = tokio::task::spawn(async move {
-    simulate_receiver(receiver).await?;
+    simulate_receiver(receiver, clock, latency).await?;
     Ok(())
   });
 
-  display_devices(client, &options, sender).await?;
+  display_devices(client.clone(), &options, sender).await?;
+  if let Err(err) = leave_workspace(&mut client, &options).await {
+    tracing::error!("Unable to leave the workspace: {}", err);
+  }
+  telemetry::shutdown();
 
   // TODO: figure out how to gracefully close the connections...
   die_early();
 
-  let futures = vec![subscribe_task, relay_task, target_task, simulate_task];
+  let futures = vec![
+    subscribe_task,
+    relay_task,
+    target_task,
+    simulate_task,
+    heartbeat_task,
+    latency_task,
+  ];
   futures::future::join_all(futures).await;
 
   Ok(())
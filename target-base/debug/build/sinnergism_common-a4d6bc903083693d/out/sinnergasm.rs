@@ -0,0 +1,2437 @@
+/// ////////////////////
+/// Create
+/// ////////////////////
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreatedResponse {}
+/// ////////////////////
+/// List
+/// ////////////////////
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WorkspaceList {
+    #[prost(message, repeated, tag = "1")]
+    pub workspaces: ::prost::alloc::vec::Vec<WorkspaceSummary>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WorkspaceSummary {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub number_of_monitors: u32,
+    #[prost(uint32, tag = "3")]
+    pub devices: u32,
+}
+/// ////////////////////
+/// Get
+/// ////////////////////
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Workspace {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub controller: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub target: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "4")]
+    pub devices: ::prost::alloc::vec::Vec<Device>,
+    #[prost(message, repeated, tag = "5")]
+    pub monitors: ::prost::alloc::vec::Vec<Monitor>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Monitor {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub x: u32,
+    #[prost(uint32, tag = "3")]
+    pub y: u32,
+    #[prost(uint32, tag = "4")]
+    pub w: u32,
+    #[prost(uint32, tag = "5")]
+    pub h: u32,
+    #[prost(string, tag = "6")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Device {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub controller: bool,
+    #[prost(message, repeated, tag = "3")]
+    pub files: ::prost::alloc::vec::Vec<SharedFile>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SharedFile {
+    #[prost(string, tag = "1")]
+    pub relative_path: ::prost::alloc::string::String,
+    #[prost(uint64, optional, tag = "2")]
+    pub size: ::core::option::Option<u64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigurationRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfiguredResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TargetRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub device: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "4")]
+    pub clipboard: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TargetResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct JoinRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub device: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "4")]
+    pub monitors: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct JoinResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LeaveRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LeaveResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ControlRequest {
+    #[prost(oneof = "control_request::EventType", tags = "1, 2")]
+    pub event_type: ::core::option::Option<control_request::EventType>,
+}
+/// Nested message and enum types in `ControlRequest`.
+pub mod control_request {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum EventType {
+        #[prost(message, tag = "1")]
+        InputEvent(super::UserInputEvent),
+        #[prost(message, tag = "2")]
+        Workspace(super::ControlWorkspace),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ControlWorkspace {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    /// TODO: this should be determined
+    #[prost(string, tag = "2")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ControlResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigurationUpdate {
+    #[prost(message, optional, tag = "1")]
+    pub workspace: ::core::option::Option<Workspace>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WorkspaceSubscriptionRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WorkspaceEvent {
+    #[prost(oneof = "workspace_event::EventType", tags = "1, 2, 3, 4, 5, 6, 7")]
+    pub event_type: ::core::option::Option<workspace_event::EventType>,
+}
+/// Nested message and enum types in `WorkspaceEvent`.
+pub mod workspace_event {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum EventType {
+        #[prost(message, tag = "1")]
+        ConfigurationUpdate(super::ConfigurationUpdate),
+        #[prost(message, tag = "2")]
+        Targetted(super::Targetted),
+        #[prost(message, tag = "3")]
+        Untargetted(super::Untargetted),
+        #[prost(message, tag = "4")]
+        TargetUpdate(super::TargetUpdate),
+        #[prost(message, tag = "5")]
+        DeviceConnected(super::DeviceConnected),
+        #[prost(message, tag = "6")]
+        DeviceDisconnected(super::DeviceDisconnected),
+        #[prost(message, tag = "7")]
+        DownloadRequest(super::UploadRequested),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Targetted {
+    #[prost(string, optional, tag = "2")]
+    pub clipboard: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Untargetted {
+    #[prost(string, tag = "1")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TargetUpdate {
+    #[prost(string, tag = "1")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeviceConnected {
+    #[prost(string, tag = "1")]
+    pub device: ::prost::alloc::string::String,
+    /// Wrap this under the configuration udpate?
+    #[prost(enumeration = "DeviceRole", tag = "2")]
+    pub role: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeviceDisconnected {
+    #[prost(string, tag = "1")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UploadRequested {
+    #[prost(string, tag = "1")]
+    pub download_device: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub relative_path: ::prost::alloc::string::String,
+    #[prost(uint64, optional, tag = "3")]
+    pub buffer_size: ::core::option::Option<u64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SimulateRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SimulationEvent {
+    #[prost(message, optional, tag = "1")]
+    pub input_event: ::core::option::Option<UserInputEvent>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserInputEvent {
+    #[prost(oneof = "user_input_event::Type", tags = "1, 2, 3, 4, 5, 6")]
+    pub r#type: ::core::option::Option<user_input_event::Type>,
+}
+/// Nested message and enum types in `UserInputEvent`.
+pub mod user_input_event {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Type {
+        #[prost(message, tag = "1")]
+        MouseMove(super::MouseMoveEvent),
+        #[prost(message, tag = "2")]
+        MousePress(super::MouseButton),
+        #[prost(message, tag = "3")]
+        MouseRelease(super::MouseButton),
+        #[prost(message, tag = "4")]
+        KeyPress(super::Key),
+        #[prost(message, tag = "5")]
+        KeyRelease(super::Key),
+        #[prost(message, tag = "6")]
+        Wheel(super::WheelEvent),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MouseMoveEvent {
+    /// double offset_x = 1;
+    /// double offset_y = 2;
+    #[prost(double, tag = "1")]
+    pub delta_x: f64,
+    #[prost(double, tag = "2")]
+    pub delta_y: f64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WheelEvent {
+    /// ... details
+    #[prost(sint32, tag = "1")]
+    pub dx: i32,
+    #[prost(sint32, tag = "2")]
+    pub dy: i32,
+}
+/// Subscriptions
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelSimulationRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelSimulationResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelSubscriptionRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelSubscriptionResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MouseButton {
+    #[prost(oneof = "mouse_button::Type", tags = "1, 2")]
+    pub r#type: ::core::option::Option<mouse_button::Type>,
+}
+/// Nested message and enum types in `MouseButton`.
+pub mod mouse_button {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Type {
+        #[prost(enumeration = "super::MouseButtons", tag = "1")]
+        Button(i32),
+        #[prost(uint32, tag = "2")]
+        Other(u32),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Key {
+    #[prost(oneof = "key::Key", tags = "1, 2")]
+    pub key: ::core::option::Option<key::Key>,
+}
+/// Nested message and enum types in `Key`.
+pub mod key {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Key {
+        #[prost(enumeration = "super::KeyCode", tag = "1")]
+        Code(i32),
+        #[prost(uint32, tag = "2")]
+        Other(u32),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DownloadRequest {
+    #[prost(oneof = "download_request::Type", tags = "1, 2, 3")]
+    pub r#type: ::core::option::Option<download_request::Type>,
+}
+/// Nested message and enum types in `DownloadRequest`.
+pub mod download_request {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Type {
+        #[prost(message, tag = "1")]
+        Initiate(super::InitiateDownload),
+        #[prost(message, tag = "2")]
+        Request(super::ChunkRequest),
+        #[prost(message, tag = "3")]
+        Complete(super::DownloadComplete),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InitiateDownload {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub download_device: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub upload_device: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub relative_path: ::prost::alloc::string::String,
+    #[prost(uint64, optional, tag = "5")]
+    pub buffer_size: ::core::option::Option<u64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChunkRequest {
+    #[prost(uint64, tag = "1")]
+    pub offset: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DownloadComplete {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DownloadResponse {
+    #[prost(oneof = "download_response::Type", tags = "1, 2")]
+    pub r#type: ::core::option::Option<download_response::Type>,
+}
+/// Nested message and enum types in `DownloadResponse`.
+pub mod download_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Type {
+        #[prost(message, tag = "1")]
+        Initated(super::DownloadInitated),
+        #[prost(message, tag = "2")]
+        Chunk(super::SharedFileChunk),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DownloadInitated {
+    #[prost(uint64, tag = "1")]
+    pub number_of_chunks: u64,
+    #[prost(uint64, tag = "2")]
+    pub buffer_size: u64,
+    #[prost(string, tag = "3")]
+    pub checksum: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "4")]
+    pub permissions: ::core::option::Option<FilePermissions>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FilePermissions {
+    #[prost(bool, tag = "1")]
+    pub execute: bool,
+    #[prost(uint32, optional, tag = "2")]
+    pub permissions: ::core::option::Option<u32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SharedFileChunk {
+    #[prost(uint64, tag = "1")]
+    pub offset: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UploadRequest {
+    #[prost(oneof = "upload_request::Type", tags = "1, 2")]
+    pub r#type: ::core::option::Option<upload_request::Type>,
+}
+/// Nested message and enum types in `UploadRequest`.
+pub mod upload_request {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Type {
+        #[prost(message, tag = "1")]
+        Initiate(super::InitiateUpload),
+        #[prost(message, tag = "2")]
+        Chunk(super::SharedFileChunk),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InitiateUpload {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub download_device: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub upload_device: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub relative_path: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "5")]
+    pub buffer_size: u64,
+    #[prost(string, tag = "6")]
+    pub checksum: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "7")]
+    pub number_of_chunks: u64,
+    #[prost(message, optional, tag = "8")]
+    pub permissions: ::core::option::Option<FilePermissions>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UploadResponse {
+    #[prost(oneof = "upload_response::Type", tags = "1, 2")]
+    pub r#type: ::core::option::Option<upload_response::Type>,
+}
+/// Nested message and enum types in `UploadResponse`.
+pub mod upload_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Type {
+        #[prost(message, tag = "1")]
+        Request(super::ChunkRequest),
+        #[prost(message, tag = "2")]
+        Complete(super::UploadComplete),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UploadComplete {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShareFileRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub device: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub relative_path: ::prost::alloc::string::String,
+    #[prost(uint64, optional, tag = "4")]
+    pub size: ::core::option::Option<u64>,
+    #[prost(message, optional, tag = "5")]
+    pub permissions: ::core::option::Option<FilePermissions>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShareFileResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RemoveSharedFileRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub device: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub relative_path: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RemoveSharedFileResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CloseRequest {
+    #[prost(string, tag = "1")]
+    pub workspace: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CloseResponse {}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum DeviceRole {
+    Unknown = 0,
+    Controller = 1,
+    Simulator = 2,
+    Subscriber = 3,
+}
+impl DeviceRole {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            DeviceRole::Unknown => "UNKNOWN",
+            DeviceRole::Controller => "CONTROLLER",
+            DeviceRole::Simulator => "SIMULATOR",
+            DeviceRole::Subscriber => "SUBSCRIBER",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "UNKNOWN" => Some(Self::Unknown),
+            "CONTROLLER" => Some(Self::Controller),
+            "SIMULATOR" => Some(Self::Simulator),
+            "SUBSCRIBER" => Some(Self::Subscriber),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum MouseButtons {
+    UnknownButton = 0,
+    Left = 1,
+    Right = 2,
+    Middle = 3,
+}
+impl MouseButtons {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            MouseButtons::UnknownButton => "UNKNOWN_BUTTON",
+            MouseButtons::Left => "LEFT",
+            MouseButtons::Right => "RIGHT",
+            MouseButtons::Middle => "MIDDLE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "UNKNOWN_BUTTON" => Some(Self::UnknownButton),
+            "LEFT" => Some(Self::Left),
+            "RIGHT" => Some(Self::Right),
+            "MIDDLE" => Some(Self::Middle),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum KeyCode {
+    UnknownKey = 0,
+    Alt = 1,
+    Altgr = 2,
+    Backspace = 3,
+    Capslock = 4,
+    Controlleft = 5,
+    Controlright = 6,
+    Delete = 7,
+    Downarrow = 8,
+    End = 9,
+    Escape = 10,
+    F1 = 11,
+    F10 = 12,
+    F11 = 13,
+    F12 = 14,
+    F2 = 15,
+    F3 = 16,
+    F4 = 17,
+    F5 = 18,
+    F6 = 19,
+    F7 = 20,
+    F8 = 21,
+    F9 = 22,
+    Home = 23,
+    Leftarrow = 24,
+    Metaleft = 25,
+    Metaright = 26,
+    Pagedown = 27,
+    Pageup = 28,
+    Return = 29,
+    Rightarrow = 30,
+    Shiftleft = 31,
+    Shiftright = 32,
+    Space = 33,
+    Tab = 34,
+    Uparrow = 35,
+    Printscreen = 36,
+    Scrolllock = 37,
+    Pause = 38,
+    Numlock = 39,
+    Backquote = 40,
+    Num1 = 41,
+    Num2 = 42,
+    Num3 = 43,
+    Num4 = 44,
+    Num5 = 45,
+    Num6 = 46,
+    Num7 = 47,
+    Num8 = 48,
+    Num9 = 49,
+    Num0 = 50,
+    Minus = 51,
+    Equal = 52,
+    Keyq = 53,
+    Keyw = 54,
+    Keye = 55,
+    Keyr = 56,
+    Keyt = 57,
+    Keyy = 58,
+    Keyu = 59,
+    Keyi = 60,
+    Keyo = 61,
+    Keyp = 62,
+    Leftbracket = 63,
+    Rightbracket = 64,
+    Keya = 65,
+    Keys = 66,
+    Keyd = 67,
+    Keyf = 68,
+    Keyg = 69,
+    Keyh = 70,
+    Keyj = 71,
+    Keyk = 72,
+    Keyl = 73,
+    Semicolon = 74,
+    Quote = 75,
+    Backslash = 76,
+    Intlbackslash = 77,
+    Keyz = 78,
+    Keyx = 79,
+    Keyc = 80,
+    Keyv = 81,
+    Keyb = 82,
+    Keyn = 83,
+    Keym = 84,
+    Comma = 85,
+    Dot = 86,
+    Slash = 87,
+    Insert = 88,
+    Kpreturn = 89,
+    Kpminus = 90,
+    Kpplus = 91,
+    Kpmultiply = 92,
+    Kpdivide = 93,
+    Kp0 = 94,
+    Kp1 = 95,
+    Kp2 = 96,
+    Kp3 = 97,
+    Kp4 = 98,
+    Kp5 = 99,
+    Kp6 = 100,
+    Kp7 = 101,
+    Kp8 = 102,
+    Kp9 = 103,
+    Kpdelete = 104,
+    Function = 105,
+}
+impl KeyCode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            KeyCode::UnknownKey => "UNKNOWN_KEY",
+            KeyCode::Alt => "ALT",
+            KeyCode::Altgr => "ALTGR",
+            KeyCode::Backspace => "BACKSPACE",
+            KeyCode::Capslock => "CAPSLOCK",
+            KeyCode::Controlleft => "CONTROLLEFT",
+            KeyCode::Controlright => "CONTROLRIGHT",
+            KeyCode::Delete => "DELETE",
+            KeyCode::Downarrow => "DOWNARROW",
+            KeyCode::End => "END",
+            KeyCode::Escape => "ESCAPE",
+            KeyCode::F1 => "F1",
+            KeyCode::F10 => "F10",
+            KeyCode::F11 => "F11",
+            KeyCode::F12 => "F12",
+            KeyCode::F2 => "F2",
+            KeyCode::F3 => "F3",
+            KeyCode::F4 => "F4",
+            KeyCode::F5 => "F5",
+            KeyCode::F6 => "F6",
+            KeyCode::F7 => "F7",
+            KeyCode::F8 => "F8",
+            KeyCode::F9 => "F9",
+            KeyCode::Home => "HOME",
+            KeyCode::Leftarrow => "LEFTARROW",
+            KeyCode::Metaleft => "METALEFT",
+            KeyCode::Metaright => "METARIGHT",
+            KeyCode::Pagedown => "PAGEDOWN",
+            KeyCode::Pageup => "PAGEUP",
+            KeyCode::Return => "RETURN",
+            KeyCode::Rightarrow => "RIGHTARROW",
+            KeyCode::Shiftleft => "SHIFTLEFT",
+            KeyCode::Shiftright => "SHIFTRIGHT",
+            KeyCode::Space => "SPACE",
+            KeyCode::Tab => "TAB",
+            KeyCode::Uparrow => "UPARROW",
+            KeyCode::Printscreen => "PRINTSCREEN",
+            KeyCode::Scrolllock => "SCROLLLOCK",
+            KeyCode::Pause => "PAUSE",
+            KeyCode::Numlock => "NUMLOCK",
+            KeyCode::Backquote => "BACKQUOTE",
+            KeyCode::Num1 => "NUM1",
+            KeyCode::Num2 => "NUM2",
+            KeyCode::Num3 => "NUM3",
+            KeyCode::Num4 => "NUM4",
+            KeyCode::Num5 => "NUM5",
+            KeyCode::Num6 => "NUM6",
+            KeyCode::Num7 => "NUM7",
+            KeyCode::Num8 => "NUM8",
+            KeyCode::Num9 => "NUM9",
+            KeyCode::Num0 => "NUM0",
+            KeyCode::Minus => "MINUS",
+            KeyCode::Equal => "EQUAL",
+            KeyCode::Keyq => "KEYQ",
+            KeyCode::Keyw => "KEYW",
+            KeyCode::Keye => "KEYE",
+            KeyCode::Keyr => "KEYR",
+            KeyCode::Keyt => "KEYT",
+            KeyCode::Keyy => "KEYY",
+            KeyCode::Keyu => "KEYU",
+            KeyCode::Keyi => "KEYI",
+            KeyCode::Keyo => "KEYO",
+            KeyCode::Keyp => "KEYP",
+            KeyCode::Leftbracket => "LEFTBRACKET",
+            KeyCode::Rightbracket => "RIGHTBRACKET",
+            KeyCode::Keya => "KEYA",
+            KeyCode::Keys => "KEYS",
+            KeyCode::Keyd => "KEYD",
+            KeyCode::Keyf => "KEYF",
+            KeyCode::Keyg => "KEYG",
+            KeyCode::Keyh => "KEYH",
+            KeyCode::Keyj => "KEYJ",
+            KeyCode::Keyk => "KEYK",
+            KeyCode::Keyl => "KEYL",
+            KeyCode::Semicolon => "SEMICOLON",
+            KeyCode::Quote => "QUOTE",
+            KeyCode::Backslash => "BACKSLASH",
+            KeyCode::Intlbackslash => "INTLBACKSLASH",
+            KeyCode::Keyz => "KEYZ",
+            KeyCode::Keyx => "KEYX",
+            KeyCode::Keyc => "KEYC",
+            KeyCode::Keyv => "KEYV",
+            KeyCode::Keyb => "KEYB",
+            KeyCode::Keyn => "KEYN",
+            KeyCode::Keym => "KEYM",
+            KeyCode::Comma => "COMMA",
+            KeyCode::Dot => "DOT",
+            KeyCode::Slash => "SLASH",
+            KeyCode::Insert => "INSERT",
+            KeyCode::Kpreturn => "KPRETURN",
+            KeyCode::Kpminus => "KPMINUS",
+            KeyCode::Kpplus => "KPPLUS",
+            KeyCode::Kpmultiply => "KPMULTIPLY",
+            KeyCode::Kpdivide => "KPDIVIDE",
+            KeyCode::Kp0 => "KP0",
+            KeyCode::Kp1 => "KP1",
+            KeyCode::Kp2 => "KP2",
+            KeyCode::Kp3 => "KP3",
+            KeyCode::Kp4 => "KP4",
+            KeyCode::Kp5 => "KP5",
+            KeyCode::Kp6 => "KP6",
+            KeyCode::Kp7 => "KP7",
+            KeyCode::Kp8 => "KP8",
+            KeyCode::Kp9 => "KP9",
+            KeyCode::Kpdelete => "KPDELETE",
+            KeyCode::Function => "FUNCTION",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "UNKNOWN_KEY" => Some(Self::UnknownKey),
+            "ALT" => Some(Self::Alt),
+            "ALTGR" => Some(Self::Altgr),
+            "BACKSPACE" => Some(Self::Backspace),
+            "CAPSLOCK" => Some(Self::Capslock),
+            "CONTROLLEFT" => Some(Self::Controlleft),
+            "CONTROLRIGHT" => Some(Self::Controlright),
+            "DELETE" => Some(Self::Delete),
+            "DOWNARROW" => Some(Self::Downarrow),
+            "END" => Some(Self::End),
+            "ESCAPE" => Some(Self::Escape),
+            "F1" => Some(Self::F1),
+            "F10" => Some(Self::F10),
+            "F11" => Some(Self::F11),
+            "F12" => Some(Self::F12),
+            "F2" => Some(Self::F2),
+            "F3" => Some(Self::F3),
+            "F4" => Some(Self::F4),
+            "F5" => Some(Self::F5),
+            "F6" => Some(Self::F6),
+            "F7" => Some(Self::F7),
+            "F8" => Some(Self::F8),
+            "F9" => Some(Self::F9),
+            "HOME" => Some(Self::Home),
+            "LEFTARROW" => Some(Self::Leftarrow),
+            "METALEFT" => Some(Self::Metaleft),
+            "METARIGHT" => Some(Self::Metaright),
+            "PAGEDOWN" => Some(Self::Pagedown),
+            "PAGEUP" => Some(Self::Pageup),
+            "RETURN" => Some(Self::Return),
+            "RIGHTARROW" => Some(Self::Rightarrow),
+            "SHIFTLEFT" => Some(Self::Shiftleft),
+            "SHIFTRIGHT" => Some(Self::Shiftright),
+            "SPACE" => Some(Self::Space),
+            "TAB" => Some(Self::Tab),
+            "UPARROW" => Some(Self::Uparrow),
+            "PRINTSCREEN" => Some(Self::Printscreen),
+            "SCROLLLOCK" => Some(Self::Scrolllock),
+            "PAUSE" => Some(Self::Pause),
+            "NUMLOCK" => Some(Self::Numlock),
+            "BACKQUOTE" => Some(Self::Backquote),
+            "NUM1" => Some(Self::Num1),
+            "NUM2" => Some(Self::Num2),
+            "NUM3" => Some(Self::Num3),
+            "NUM4" => Some(Self::Num4),
+            "NUM5" => Some(Self::Num5),
+            "NUM6" => Some(Self::Num6),
+            "NUM7" => Some(Self::Num7),
+            "NUM8" => Some(Self::Num8),
+            "NUM9" => Some(Self::Num9),
+            "NUM0" => Some(Self::Num0),
+            "MINUS" => Some(Self::Minus),
+            "EQUAL" => Some(Self::Equal),
+            "KEYQ" => Some(Self::Keyq),
+            "KEYW" => Some(Self::Keyw),
+            "KEYE" => Some(Self::Keye),
+            "KEYR" => Some(Self::Keyr),
+            "KEYT" => Some(Self::Keyt),
+            "KEYY" => Some(Self::Keyy),
+            "KEYU" => Some(Self::Keyu),
+            "KEYI" => Some(Self::Keyi),
+            "KEYO" => Some(Self::Keyo),
+            "KEYP" => Some(Self::Keyp),
+            "LEFTBRACKET" => Some(Self::Leftbracket),
+            "RIGHTBRACKET" => Some(Self::Rightbracket),
+            "KEYA" => Some(Self::Keya),
+            "KEYS" => Some(Self::Keys),
+            "KEYD" => Some(Self::Keyd),
+            "KEYF" => Some(Self::Keyf),
+            "KEYG" => Some(Self::Keyg),
+            "KEYH" => Some(Self::Keyh),
+            "KEYJ" => Some(Self::Keyj),
+            "KEYK" => Some(Self::Keyk),
+            "KEYL" => Some(Self::Keyl),
+            "SEMICOLON" => Some(Self::Semicolon),
+            "QUOTE" => Some(Self::Quote),
+            "BACKSLASH" => Some(Self::Backslash),
+            "INTLBACKSLASH" => Some(Self::Intlbackslash),
+            "KEYZ" => Some(Self::Keyz),
+            "KEYX" => Some(Self::Keyx),
+            "KEYC" => Some(Self::Keyc),
+            "KEYV" => Some(Self::Keyv),
+            "KEYB" => Some(Self::Keyb),
+            "KEYN" => Some(Self::Keyn),
+            "KEYM" => Some(Self::Keym),
+            "COMMA" => Some(Self::Comma),
+            "DOT" => Some(Self::Dot),
+            "SLASH" => Some(Self::Slash),
+            "INSERT" => Some(Self::Insert),
+            "KPRETURN" => Some(Self::Kpreturn),
+            "KPMINUS" => Some(Self::Kpminus),
+            "KPPLUS" => Some(Self::Kpplus),
+            "KPMULTIPLY" => Some(Self::Kpmultiply),
+            "KPDIVIDE" => Some(Self::Kpdivide),
+            "KP0" => Some(Self::Kp0),
+            "KP1" => Some(Self::Kp1),
+            "KP2" => Some(Self::Kp2),
+            "KP3" => Some(Self::Kp3),
+            "KP4" => Some(Self::Kp4),
+            "KP5" => Some(Self::Kp5),
+            "KP6" => Some(Self::Kp6),
+            "KP7" => Some(Self::Kp7),
+            "KP8" => Some(Self::Kp8),
+            "KP9" => Some(Self::Kp9),
+            "KPDELETE" => Some(Self::Kpdelete),
+            "FUNCTION" => Some(Self::Function),
+            _ => None,
+        }
+    }
+}
+/// Generated client implementations.
+pub mod virtual_workspaces_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct VirtualWorkspacesClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl VirtualWorkspacesClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> VirtualWorkspacesClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> VirtualWorkspacesClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            VirtualWorkspacesClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn create_workspace(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreatedResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/CreateWorkspace",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "CreateWorkspace"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_workspaces(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListRequest>,
+        ) -> std::result::Result<tonic::Response<super::WorkspaceList>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/ListWorkspaces",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "ListWorkspaces"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_workspace(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetRequest>,
+        ) -> std::result::Result<tonic::Response<super::Workspace>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/GetWorkspace",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("sinnergasm.VirtualWorkspaces", "GetWorkspace"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_workspace(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteRequest>,
+        ) -> std::result::Result<tonic::Response<super::DeleteResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/DeleteWorkspace",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "DeleteWorkspace"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn configure_workspace(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ConfigurationRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ConfiguredResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/ConfigureWorkspace",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "ConfigureWorkspace"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn share_file(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ShareFileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShareFileResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/ShareFile",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("sinnergasm.VirtualWorkspaces", "ShareFile"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn remove_shared_file(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RemoveSharedFileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RemoveSharedFileResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/RemoveSharedFile",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "RemoveSharedFile"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn close_workspace(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CloseRequest>,
+        ) -> std::result::Result<tonic::Response<super::CloseResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/CloseWorkspace",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "CloseWorkspace"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn target_device(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TargetRequest>,
+        ) -> std::result::Result<tonic::Response<super::TargetResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/TargetDevice",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("sinnergasm.VirtualWorkspaces", "TargetDevice"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn control_workspace(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::ControlRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ControlResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/ControlWorkspace",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "ControlWorkspace"),
+                );
+            self.inner.client_streaming(req, path, codec).await
+        }
+        pub async fn simulate_workspace(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SimulateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::SimulationEvent>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/SimulateWorkspace",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "SimulateWorkspace"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn cancel_simulation(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CancelSimulationRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelSimulationResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/CancelSimulation",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "CancelSimulation"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn subscribe_to_workspace(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WorkspaceSubscriptionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::WorkspaceEvent>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/SubscribeToWorkspace",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "sinnergasm.VirtualWorkspaces",
+                        "SubscribeToWorkspace",
+                    ),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn cancel_subscription(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CancelSubscriptionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelSubscriptionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/CancelSubscription",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("sinnergasm.VirtualWorkspaces", "CancelSubscription"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn download_file(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::DownloadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::DownloadResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/DownloadFile",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("sinnergasm.VirtualWorkspaces", "DownloadFile"));
+            self.inner.streaming(req, path, codec).await
+        }
+        pub async fn upload_file(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::UploadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::UploadResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/sinnergasm.VirtualWorkspaces/UploadFile",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("sinnergasm.VirtualWorkspaces", "UploadFile"));
+            self.inner.streaming(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod virtual_workspaces_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with VirtualWorkspacesServer.
+    #[async_trait]
+    pub trait VirtualWorkspaces: Send + Sync + 'static {
+        async fn create_workspace(
+            &self,
+            request: tonic::Request<super::CreateRequest>,
+        ) -> std::result::Result<tonic::Response<super::CreatedResponse>, tonic::Status>;
+        async fn list_workspaces(
+            &self,
+            request: tonic::Request<super::ListRequest>,
+        ) -> std::result::Result<tonic::Response<super::WorkspaceList>, tonic::Status>;
+        async fn get_workspace(
+            &self,
+            request: tonic::Request<super::GetRequest>,
+        ) -> std::result::Result<tonic::Response<super::Workspace>, tonic::Status>;
+        async fn delete_workspace(
+            &self,
+            request: tonic::Request<super::DeleteRequest>,
+        ) -> std::result::Result<tonic::Response<super::DeleteResponse>, tonic::Status>;
+        async fn configure_workspace(
+            &self,
+            request: tonic::Request<super::ConfigurationRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ConfiguredResponse>,
+            tonic::Status,
+        >;
+        async fn share_file(
+            &self,
+            request: tonic::Request<super::ShareFileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShareFileResponse>,
+            tonic::Status,
+        >;
+        async fn remove_shared_file(
+            &self,
+            request: tonic::Request<super::RemoveSharedFileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RemoveSharedFileResponse>,
+            tonic::Status,
+        >;
+        async fn close_workspace(
+            &self,
+            request: tonic::Request<super::CloseRequest>,
+        ) -> std::result::Result<tonic::Response<super::CloseResponse>, tonic::Status>;
+        async fn target_device(
+            &self,
+            request: tonic::Request<super::TargetRequest>,
+        ) -> std::result::Result<tonic::Response<super::TargetResponse>, tonic::Status>;
+        async fn control_workspace(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::ControlRequest>>,
+        ) -> std::result::Result<tonic::Response<super::ControlResponse>, tonic::Status>;
+        /// Server streaming response type for the SimulateWorkspace method.
+        type SimulateWorkspaceStream: futures_core::Stream<
+                Item = std::result::Result<super::SimulationEvent, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn simulate_workspace(
+            &self,
+            request: tonic::Request<super::SimulateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SimulateWorkspaceStream>,
+            tonic::Status,
+        >;
+        async fn cancel_simulation(
+            &self,
+            request: tonic::Request<super::CancelSimulationRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelSimulationResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the SubscribeToWorkspace method.
+        type SubscribeToWorkspaceStream: futures_core::Stream<
+                Item = std::result::Result<super::WorkspaceEvent, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn subscribe_to_workspace(
+            &self,
+            request: tonic::Request<super::WorkspaceSubscriptionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SubscribeToWorkspaceStream>,
+            tonic::Status,
+        >;
+        async fn cancel_subscription(
+            &self,
+            request: tonic::Request<super::CancelSubscriptionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelSubscriptionResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the DownloadFile method.
+        type DownloadFileStream: futures_core::Stream<
+                Item = std::result::Result<super::DownloadResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn download_file(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::DownloadRequest>>,
+        ) -> std::result::Result<
+            tonic::Response<Self::DownloadFileStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the UploadFile method.
+        type UploadFileStream: futures_core::Stream<
+                Item = std::result::Result<super::UploadResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn upload_file(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::UploadRequest>>,
+        ) -> std::result::Result<tonic::Response<Self::UploadFileStream>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct VirtualWorkspacesServer<T: VirtualWorkspaces> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: VirtualWorkspaces> VirtualWorkspacesServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for VirtualWorkspacesServer<T>
+    where
+        T: VirtualWorkspaces,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/sinnergasm.VirtualWorkspaces/CreateWorkspace" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateWorkspaceSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::CreateRequest>
+                    for CreateWorkspaceSvc<T> {
+                        type Response = super::CreatedResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).create_workspace(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateWorkspaceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/ListWorkspaces" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListWorkspacesSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::ListRequest>
+                    for ListWorkspacesSvc<T> {
+                        type Response = super::WorkspaceList;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).list_workspaces(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListWorkspacesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/GetWorkspace" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetWorkspaceSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::GetRequest>
+                    for GetWorkspaceSvc<T> {
+                        type Response = super::Workspace;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).get_workspace(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetWorkspaceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/DeleteWorkspace" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteWorkspaceSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::DeleteRequest>
+                    for DeleteWorkspaceSvc<T> {
+                        type Response = super::DeleteResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).delete_workspace(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteWorkspaceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/ConfigureWorkspace" => {
+                    #[allow(non_camel_case_types)]
+                    struct ConfigureWorkspaceSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::ConfigurationRequest>
+                    for ConfigureWorkspaceSvc<T> {
+                        type Response = super::ConfiguredResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ConfigurationRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).configure_workspace(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ConfigureWorkspaceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/ShareFile" => {
+                    #[allow(non_camel_case_types)]
+                    struct ShareFileSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::ShareFileRequest>
+                    for ShareFileSvc<T> {
+                        type Response = super::ShareFileResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ShareFileRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).share_file(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ShareFileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/RemoveSharedFile" => {
+                    #[allow(non_camel_case_types)]
+                    struct RemoveSharedFileSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::RemoveSharedFileRequest>
+                    for RemoveSharedFileSvc<T> {
+                        type Response = super::RemoveSharedFileResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RemoveSharedFileRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).remove_shared_file(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RemoveSharedFileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/CloseWorkspace" => {
+                    #[allow(non_camel_case_types)]
+                    struct CloseWorkspaceSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::CloseRequest>
+                    for CloseWorkspaceSvc<T> {
+                        type Response = super::CloseResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CloseRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).close_workspace(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CloseWorkspaceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/TargetDevice" => {
+                    #[allow(non_camel_case_types)]
+                    struct TargetDeviceSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::TargetRequest>
+                    for TargetDeviceSvc<T> {
+                        type Response = super::TargetResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TargetRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).target_device(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = TargetDeviceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/ControlWorkspace" => {
+                    #[allow(non_camel_case_types)]
+                    struct ControlWorkspaceSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::ClientStreamingService<super::ControlRequest>
+                    for ControlWorkspaceSvc<T> {
+                        type Response = super::ControlResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::ControlRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).control_workspace(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ControlWorkspaceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/SimulateWorkspace" => {
+                    #[allow(non_camel_case_types)]
+                    struct SimulateWorkspaceSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::ServerStreamingService<super::SimulateRequest>
+                    for SimulateWorkspaceSvc<T> {
+                        type Response = super::SimulationEvent;
+                        type ResponseStream = T::SimulateWorkspaceStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SimulateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).simulate_workspace(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SimulateWorkspaceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/CancelSimulation" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelSimulationSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::CancelSimulationRequest>
+                    for CancelSimulationSvc<T> {
+                        type Response = super::CancelSimulationResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelSimulationRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).cancel_simulation(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelSimulationSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/SubscribeToWorkspace" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeToWorkspaceSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::ServerStreamingService<
+                        super::WorkspaceSubscriptionRequest,
+                    > for SubscribeToWorkspaceSvc<T> {
+                        type Response = super::WorkspaceEvent;
+                        type ResponseStream = T::SubscribeToWorkspaceStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WorkspaceSubscriptionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).subscribe_to_workspace(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubscribeToWorkspaceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/CancelSubscription" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelSubscriptionSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::UnaryService<super::CancelSubscriptionRequest>
+                    for CancelSubscriptionSvc<T> {
+                        type Response = super::CancelSubscriptionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelSubscriptionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).cancel_subscription(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelSubscriptionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/DownloadFile" => {
+                    #[allow(non_camel_case_types)]
+                    struct DownloadFileSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::StreamingService<super::DownloadRequest>
+                    for DownloadFileSvc<T> {
+                        type Response = super::DownloadResponse;
+                        type ResponseStream = T::DownloadFileStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::DownloadRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).download_file(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DownloadFileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sinnergasm.VirtualWorkspaces/UploadFile" => {
+                    #[allow(non_camel_case_types)]
+                    struct UploadFileSvc<T: VirtualWorkspaces>(pub Arc<T>);
+                    impl<
+                        T: VirtualWorkspaces,
+                    > tonic::server::StreamingService<super::UploadRequest>
+                    for UploadFileSvc<T> {
+                        type Response = super::UploadResponse;
+                        type ResponseStream = T::UploadFileStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::UploadRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).upload_file(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UploadFileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: VirtualWorkspaces> Clone for VirtualWorkspacesServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: VirtualWorkspaces> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: VirtualWorkspaces> tonic::server::NamedService
+    for VirtualWorkspacesServer<T> {
+        const NAME: &'static str = "sinnergasm.VirtualWorkspaces";
+    }
+}
@@ -0,0 +1,49 @@
+use sinnergasm::latency::now_us;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+// Only the most recent heartbeats count, so the estimate follows a drifting clock
+const SAMPLES: usize = 8;
+
+#[derive(Debug, Default)]
+struct ClockSamples {
+  // The round trip and the offset it measured, both in microseconds
+  samples: VecDeque<(u64, i64)>,
+}
+
+// How far the server clock is ahead of the local one, estimated from the heartbeat round trips
+#[derive(Debug, Clone, Default)]
+pub struct ServerClock {
+  samples: Arc<Mutex<ClockSamples>>,
+}
+
+impl ServerClock {
+  // Assumes the server answered halfway through the round trip
+  pub fn sample(&self, sent_at_us: u64, received_at_us: u64, server_time_us: u64) {
+    let round_trip = received_at_us.saturating_sub(sent_at_us);
+    let offset = server_time_us as i64 - (sent_at_us + round_trip / 2) as i64;
+    if let Ok(mut clock) = self.samples.lock() {
+      if clock.samples.len() == SAMPLES {
+        clock.samples.pop_front();
+      }
+      clock.samples.push_back((round_trip, offset));
+    }
+  }
+
+  // The sample with the shortest round trip was the least delayed by queueing, so it is the most accurate
+  pub fn offset_us(&self) -> i64 {
+    self
+      .samples
+      .lock()
+      .ok()
+      .and_then(|clock| clock.samples.iter().min_by_key(|(round_trip, _)| *round_trip).copied())
+      .map(|(_, offset)| offset)
+      .unwrap_or_default()
+  }
+
+  // Microseconds since the unix epoch on the server clock
+  pub fn now_us(&self) -> u64 {
+    now_us().saturating_add_signed(self.offset_us())
+  }
+}
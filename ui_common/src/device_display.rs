@@ -5,17 +5,24 @@ use druid::widget::Label;
 use crate::events;
 use druid::AppLauncher;
 use druid::Data;
+use druid::ExtEventSink;
 use druid::Widget;
 use druid::WidgetExt;
 use druid::WindowDesc;
 use sinnergasm::grpc_client::GrpcClient;
 use sinnergasm::options::Options;
 use sinnergasm::protos as msg;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
 use tokio::sync::broadcast::Sender;
 
 #[derive(Clone, Data)]
 struct DisplayState {
   listening: bool,
+  // Only devices with a simulator connected can be targetted
+  simulators: Arc<BTreeSet<String>>,
 }
 
 fn ui_builder(other_devices: Vec<msg::Device>, sender: Sender<events::AppEvent>) -> impl Widget<DisplayState> {
@@ -40,7 +47,8 @@ fn ui_builder(other_devices: Vec<msg::Device>, sender: Sender<events::AppEvent>)
         .send(events::AppEvent::target(device_name.clone()))
         .expect("Unable to go to queue workspace request");
     });
-    column.add_child(button);
+    let device_name = device.name.clone();
+    column.add_child(button.disabled_if(move |state: &DisplayState, _| !state.simulators.contains(&device_name)));
 
     for shared_file in device.files {
       let button_sender = sender.clone();
@@ -51,7 +59,7 @@ fn ui_builder(other_devices: Vec<msg::Device>, sender: Sender<events::AppEvent>)
         shared_file.relative_path.clone()
       );
       let button = Button::new(label).on_click(move |_ctx, _data, _env| {
-        println!("Sending app event to download {:?}", shared_file);
+        tracing::info!("Sending app event to download {:?}", shared_file);
         button_sender
           .send(events::AppEvent::RequestDwonload(
             device_name.clone(),
@@ -70,24 +78,66 @@ pub async fn display_devices(
   options: &Options,
   sender: Sender<events::AppEvent>,
 ) -> Result<(), anyhow::Error> {
-  let other_devices = {
-    let request = msg::GetRequest {
-      name: options.workspace.clone(),
-    };
-    let workspace = client.get_workspace(request).await?.into_inner();
-    println!("Connecting to workspace: {:?}", workspace);
-    workspace
-      .devices
-      .iter()
-      .filter(|device| device.name != *options.device)
-      .cloned()
-      .collect::<Vec<_>>()
+  // Subscribed before the status is read so no connection in between is missed
+  let receiver = sender.subscribe();
+  let request = msg::StatusRequest {
+    workspace: options.workspace.clone(),
+  };
+  let status = client.get_workspace_status(request).await?.into_inner();
+  tracing::debug!("Connecting to workspace: {:?}", status);
+  let other_devices = status
+    .workspace
+    .map(|workspace| workspace.devices)
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|device| device.name != *options.device)
+    .collect::<Vec<_>>();
+  let simulators = status
+    .sessions
+    .into_iter()
+    .filter(|session| session.role == msg::DeviceRole::Simulator as i32)
+    .map(|session| session.device)
+    .collect();
+
+  let display_state = DisplayState {
+    listening: false,
+    simulators: Arc::new(simulators),
   };
-  let display_state = DisplayState { listening: false };
   let ui = ui_builder(other_devices, sender);
   let main_window = WindowDesc::new(ui);
-  AppLauncher::with_window(main_window)
-    .log_to_console()
-    .launch(display_state)?;
+  let launcher = AppLauncher::with_window(main_window).log_to_console();
+  let presence_task = tokio::task::spawn(track_simulators(receiver, launcher.get_external_handle()));
+  launcher.launch(display_state)?;
+  presence_task.abort();
   Ok(())
 }
+
+async fn track_simulators(mut receiver: Receiver<events::AppEvent>, sink: ExtEventSink) {
+  loop {
+    let (device, connected) = match receiver.recv().await {
+      Ok(events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::DeviceConnected(
+        device,
+        msg::DeviceRole::Simulator,
+      ))) => (device, true),
+      Ok(events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::DeviceDisconnected(
+        device,
+        msg::DeviceRole::Simulator,
+      ))) => (device, false),
+      Ok(_) => continue,
+      Err(RecvError::Lagged(skipped)) => {
+        tracing::warn!("Device display skipped {} events", skipped);
+        continue;
+      }
+      Err(RecvError::Closed) => break,
+    };
+    sink.add_idle_callback(move |state: &mut DisplayState| {
+      let mut simulators = (*state.simulators).clone();
+      if connected {
+        simulators.insert(device);
+      } else {
+        simulators.remove(&device);
+      }
+      state.simulators = Arc::new(simulators);
+    });
+  }
+}
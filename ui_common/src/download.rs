@@ -7,6 +7,7 @@ use std::io;
 use std::io::prelude::*;
 use tokio::sync::mpsc as tokio_mpsc;
 use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::Instrument;
 
 #[derive(Debug)]
 enum ProgressCheck {
@@ -42,7 +43,7 @@ impl DownloadChunkPool {
   fn set_completed(&mut self, offset: Option<u64>) -> Result<ProgressCheck, anyhow::Error> {
     if let Some(offset) = offset {
       if let Some(index) = self.downloading.iter().position(|&(x, _)| x == offset) {
-        println!("Removing chunk {} from downloading", offset);
+        tracing::trace!("Removing chunk {} from downloading", offset);
         self.downloading.remove(index);
         self.downloaded.push(offset);
       } else {
@@ -79,15 +80,18 @@ pub async fn spawn_download_task(
   shared_file: msg::SharedFile,
   options: Arc<Options>,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
-  println!("Spawning download task");
-  let task = tokio::spawn(async move {
-    println!("Inside download task");
-    if let Err(err) = download_file(client, upload_device, shared_file, options).await {
-      eprintln!("Error downloading file: {}", err);
+  let span = tracing::info_span!("download", uploader = %upload_device, path = %shared_file.relative_path);
+  tracing::debug!(parent: &span, "Spawning download task");
+  let task = tokio::spawn(
+    async move {
+      if let Err(err) = download_file(client, upload_device, shared_file, options).await {
+        tracing::error!("Error downloading file: {}", err);
+      }
+      tracing::debug!("Download task finished");
+      Ok(())
     }
-    println!("Download task finished");
-    Ok(())
-  });
+    .instrument(span),
+  );
   return task;
 }
 
@@ -97,11 +101,9 @@ async fn download_file(
   shared_file: msg::SharedFile,
   options: Arc<Options>,
 ) -> Result<(), anyhow::Error> {
-  println!("Inside download_file");
   let (sender, receiver) = tokio_mpsc::unbounded_channel();
-  println!("Spawned thread: creating receiver stream");
   let receiver_stream = UnboundedReceiverStream::new(receiver);
-  println!("Spawned thread: sending download request");
+  tracing::debug!("Sending download request");
   sender.send(msg::DownloadRequest {
     r#type: Some(msg::download_request::Type::Initiate(msg::InitiateDownload {
       workspace: options.workspace.clone(),
@@ -111,20 +113,19 @@ async fn download_file(
       buffer_size: None,
     })),
   })?;
-  println!("Spawned thread: creating download stream");
+  tracing::debug!("Creating download stream");
   let mut stream = client.download_file(receiver_stream).await?.into_inner();
-  println!("Spawned thread: joining paths");
   let target_location = std::path::Path::new(&options.shared_folder).join(&shared_file.relative_path);
   let target_directory = target_location
     .parent()
     .expect(format!("Unable to determine parent directory: {:?}", &target_location).as_str());
-  println!("Spawned thread: creating directory");
+  tracing::debug!("Creating directory {:?}", target_directory);
   std::fs::create_dir_all(target_directory)
     .expect(format!("Unable to create directory: {:?}", &target_directory).as_str());
 
   let mut file = std::fs::File::create(&target_location)?;
 
-  println!("Spawned thread: created file");
+  tracing::debug!("Created file {:?}", target_location);
 
   if let Some(msg::DownloadResponse {
     r#type:
@@ -136,17 +137,17 @@ async fn download_file(
       })),
   }) = stream.message().await?
   {
-    println!("Received download initiated message");
+    tracing::info!(chunks = number_of_chunks, "Received download initiated message");
     let mut downloads = DownloadChunkPool::new(number_of_chunks, 1, sender);
 
     match downloads.set_completed(None) {
       Ok(ProgressCheck::Complete) => {
-        eprintln!("Download complete after initial request!!");
+        tracing::warn!("Download complete after initial request");
       }
       Ok(ProgressCheck::Requested(num_requested)) => {
-        println!("Requested initial chunks: {:?}", num_requested);
+        tracing::debug!("Requested initial chunks: {:?}", num_requested);
       }
-      Err(err) => eprintln!("Unable to send download initial requests: {:?}", err),
+      Err(err) => tracing::error!("Unable to send download initial requests: {:?}", err),
     }
 
     'outer: while let Some(msg::DownloadResponse {
@@ -158,20 +159,20 @@ async fn download_file(
           panic!("Download should only be initiated once");
         }
         msg::download_response::Type::Chunk(msg::SharedFileChunk { offset, data }) => {
-          println!("Received chunk {}", offset);
+          tracing::trace!("Received chunk {}", offset);
 
           file.seek(io::SeekFrom::Start(offset * buffer_size))?;
           file.write_all(&data)?;
 
           match downloads.set_completed(Some(offset)) {
             Ok(ProgressCheck::Complete) => {
-              println!("Download complete");
+              tracing::info!("Download complete");
               break 'outer;
             }
             Ok(ProgressCheck::Requested(num_requested)) => {
-              println!("Requesting chunks: {:?}", num_requested);
+              tracing::trace!("Requesting chunks: {:?}", num_requested);
             }
-            Err(err) => eprintln!("Unable to send download requests: {:?}", err),
+            Err(err) => tracing::error!("Unable to send download requests: {:?}", err),
           }
         }
       }
@@ -179,9 +180,9 @@ async fn download_file(
 
     let actual_checksum = crate::upload::compute_hash(&target_location)?;
     if expected_checksum == actual_checksum {
-      println!("Checksum {} matches.", expected_checksum);
+      tracing::info!("Checksum {} matches.", expected_checksum);
     } else {
-      eprintln!("Checksum mismatch! Expected: {}, found: {}", expected_checksum, actual_checksum);
+      tracing::error!("Checksum mismatch! Expected: {}, found: {}", expected_checksum, actual_checksum);
     }
   } else {
     panic!("First message should be the upload has been initiated.");
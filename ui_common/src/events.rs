@@ -1,5 +1,6 @@
 use rdev;
 use sinnergasm::protos as msg;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub enum ControllerEvent {
@@ -18,7 +19,13 @@ pub enum SubscriptionEvent {
   Targetted,
   Untargetted,
   RequestTarget(String),
-  BeginUpload(msg::UploadRequested),
+  // Symmetric mode: take control of the workspace and target this device
+  TakeControl,
+  // With the trace of the download the upload is for
+  BeginUpload(msg::UploadRequested, HashMap<String, String>),
+  ConfigurationUpdate(msg::Workspace),
+  DeviceConnected(String, msg::DeviceRole),
+  DeviceDisconnected(String, msg::DeviceRole),
 }
 
 #[derive(Debug, Clone)]
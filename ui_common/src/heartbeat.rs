@@ -0,0 +1,35 @@
+use crate::clock::ServerClock;
+use sinnergasm::grpc_client::GrpcClient;
+use sinnergasm::latency::now_us;
+use sinnergasm::options::Options;
+use sinnergasm::protos as msg;
+use std::sync::Arc;
+
+// Keeps this device from being evicted by the server while it is idle, and the clock estimate up to date
+pub async fn launch_heartbeat_task(
+  options: Arc<Options>,
+  mut client: GrpcClient,
+  clock: ServerClock,
+) -> tokio::task::JoinHandle<anyhow::Result<()>> {
+  tokio::task::spawn(async move {
+    let mut interval = tokio::time::interval(options.heartbeat_interval);
+    loop {
+      interval.tick().await;
+      let request = msg::HeartbeatRequest {
+        workspace: options.workspace.clone(),
+        device: options.device.clone(),
+      };
+      let sent_at_us = now_us();
+      match client.heartbeat(request).await {
+        Ok(response) => {
+          // Older servers do not send their time
+          let server_time_us = response.into_inner().server_time_us;
+          if server_time_us != 0 {
+            clock.sample(sent_at_us, now_us(), server_time_us);
+          }
+        }
+        Err(err) => tracing::warn!("Unable to send heartbeat: {}", err),
+      }
+    }
+  })
+}
@@ -0,0 +1,161 @@
+use sinnergasm::grpc_client::GrpcClient;
+use sinnergasm::latency;
+use sinnergasm::options::Options;
+use sinnergasm::protos as msg;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+struct Recorded {
+  report: msg::LatencyReport,
+  last_sequence: u64,
+}
+
+// Collects how long the input this device simulates took to get here
+#[derive(Debug, Clone)]
+pub struct LatencyRecorder {
+  recorded: Arc<Mutex<Recorded>>,
+}
+
+impl LatencyRecorder {
+  pub fn new(options: &Options) -> Self {
+    Self {
+      recorded: Arc::new(Mutex::new(Recorded {
+        report: latency::report(&options.workspace, &options.device),
+        last_sequence: 0,
+      })),
+    }
+  }
+
+  // Every time is on the server clock, input the server made up itself has none of them
+  pub fn record(&self, input: &msg::UserInputEvent, relayed_at_us: u64, injected_at_us: u64) {
+    if input.sequence == 0 || input.captured_at_us == 0 || relayed_at_us == 0 {
+      return;
+    }
+    let Ok(mut guard) = self.recorded.lock() else {
+      return;
+    };
+    let recorded = &mut *guard;
+    // A lower sequence comes from a new control stream
+    if recorded.last_sequence != 0 && input.sequence > recorded.last_sequence + 1 {
+      recorded.report.missed += input.sequence - recorded.last_sequence - 1;
+    }
+    recorded.last_sequence = input.sequence;
+
+    let hops = [
+      (
+        &mut recorded.report.capture_to_relay,
+        input.captured_at_us,
+        relayed_at_us,
+      ),
+      (&mut recorded.report.relay_to_inject, relayed_at_us, injected_at_us),
+      (
+        &mut recorded.report.capture_to_inject,
+        input.captured_at_us,
+        injected_at_us,
+      ),
+    ];
+    for (histogram, from, to) in hops {
+      if let Some(histogram) = histogram.as_mut() {
+        latency::record(histogram, to.saturating_sub(from));
+      }
+    }
+  }
+
+  fn report(&self) -> Option<msg::LatencyReport> {
+    let recorded = self.recorded.lock().ok()?;
+    let samples = recorded.report.capture_to_inject.as_ref().map(latency::samples);
+    matches!(samples, Some(samples) if samples > 0).then(|| recorded.report.clone())
+  }
+}
+
+// Sends what was recorded so far to the server every few seconds
+pub async fn launch_latency_reporter(
+  mut client: GrpcClient,
+  recorder: LatencyRecorder,
+) -> tokio::task::JoinHandle<anyhow::Result<()>> {
+  tokio::task::spawn(async move {
+    let mut interval = tokio::time::interval(REPORT_INTERVAL);
+    loop {
+      interval.tick().await;
+      if let Some(report) = recorder.report() {
+        if let Err(err) = client.report_latency(report).await {
+          tracing::warn!("Unable to report latency: {}", err);
+        }
+      }
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn recorder() -> LatencyRecorder {
+    LatencyRecorder {
+      recorded: Arc::new(Mutex::new(Recorded {
+        report: latency::report("workspace", "device"),
+        last_sequence: 0,
+      })),
+    }
+  }
+
+  fn input(sequence: u64) -> msg::UserInputEvent {
+    msg::UserInputEvent {
+      sequence,
+      captured_at_us: 1_000,
+      ..Default::default()
+    }
+  }
+
+  fn missed(recorder: &LatencyRecorder) -> u64 {
+    recorder.recorded.lock().unwrap().report.missed
+  }
+
+  #[test]
+  fn records_each_hop() {
+    let recorder = recorder();
+    recorder.record(&input(1), 1_300, 1_500);
+
+    let report = recorder.report().unwrap();
+    assert_eq!(report.capture_to_relay.unwrap().sum_us, 300);
+    assert_eq!(report.relay_to_inject.unwrap().sum_us, 200);
+    assert_eq!(report.capture_to_inject.unwrap().sum_us, 500);
+  }
+
+  #[test]
+  fn counts_the_gaps_in_the_sequence_as_missed() {
+    let recorder = recorder();
+    recorder.record(&input(1), 1_300, 1_500);
+    recorder.record(&input(2), 1_300, 1_500);
+    assert_eq!(missed(&recorder), 0);
+
+    recorder.record(&input(5), 1_300, 1_500);
+    assert_eq!(missed(&recorder), 2);
+
+    // A new control stream starts over without anything missed
+    recorder.record(&input(1), 1_300, 1_500);
+    recorder.record(&input(2), 1_300, 1_500);
+    assert_eq!(missed(&recorder), 2);
+  }
+
+  #[test]
+  fn leaves_out_input_without_times() {
+    let recorder = recorder();
+    recorder.record(&input(0), 1_300, 1_500);
+    recorder.record(&input(1), 0, 1_500);
+    recorder.record(
+      &msg::UserInputEvent {
+        sequence: 2,
+        ..Default::default()
+      },
+      1_300,
+      1_500,
+    );
+
+    assert!(recorder.report().is_none());
+  }
+}
@@ -1,7 +1,12 @@
+pub mod clock;
 pub mod device_display;
 pub mod download;
 pub mod errors;
 pub mod events;
+pub mod heartbeat;
+pub mod latency;
+pub mod membership;
+pub mod simulation;
 pub mod subscribe;
 pub mod target;
 pub mod translation;
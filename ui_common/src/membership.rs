@@ -0,0 +1,48 @@
+use sinnergasm::grpc_client::GrpcClient;
+use sinnergasm::options::Options;
+use sinnergasm::protos as msg;
+
+// Only the primary display is known, it is placed at the origin until the workspace is arranged
+fn local_monitors(options: &Options) -> Vec<msg::Monitor> {
+  match rdev::display_size() {
+    Ok((w, h)) => vec![msg::Monitor {
+      name: format!("{}-primary", options.device),
+      x: 0,
+      y: 0,
+      w: w as u32,
+      h: h as u32,
+      device: options.device.clone(),
+    }],
+    Err(err) => {
+      tracing::warn!("Unable to read the display size, joining without monitors: {:?}", err);
+      vec![]
+    }
+  }
+}
+
+pub async fn join_workspace(
+  client: &mut GrpcClient,
+  options: &Options,
+  controller: bool,
+) -> Result<msg::Workspace, anyhow::Error> {
+  let request = msg::JoinRequest {
+    workspace: options.workspace.clone(),
+    device: options.device.clone(),
+    monitors: local_monitors(options),
+    controller,
+  };
+  let response = client.join_workspace(request).await?.into_inner();
+  response
+    .workspace
+    .ok_or_else(|| anyhow::anyhow!("Join response did not include the workspace"))
+}
+
+pub async fn leave_workspace(client: &mut GrpcClient, options: &Options) -> Result<(), anyhow::Error> {
+  client
+    .leave_workspace(msg::LeaveRequest {
+      workspace: options.workspace.clone(),
+      device: options.device.clone(),
+    })
+    .await?;
+  Ok(())
+}
@@ -1,10 +1,15 @@
+use crate::clock::ServerClock;
+use crate::events;
+use crate::latency::LatencyRecorder;
+use crate::translation as tr;
 use anyhow;
-use anyhow::Ok;
 use rdev::simulate;
+use sinnergasm::grpc_client::GrpcClient;
+use sinnergasm::options::Options;
 use sinnergasm::protos as msg;
+use std::sync::Arc;
 use tokio::sync::broadcast::Receiver;
-use ui_common::events;
-use ui_common::translation as tr;
+use tokio::sync::broadcast::Sender;
 
 fn simulate_input_event(
   desired_position: (f64, f64),
@@ -23,7 +28,7 @@ fn simulate_input_event(
       if let Some(button) = tr::mouse_msg_to_rdev(&button) {
         simulate(&rdev::EventType::ButtonPress(button))?;
       } else {
-        println!("Unknown mouse button: {:?}", button);
+        tracing::warn!("Unknown mouse button: {:?}", button);
       }
       Ok(None)
     }
@@ -31,7 +36,7 @@ fn simulate_input_event(
       if let Some(button) = tr::mouse_msg_to_rdev(&button) {
         simulate(&rdev::EventType::ButtonRelease(button))?;
       } else {
-        println!("Unknown mouse button: {:?}", button);
+        tracing::warn!("Unknown mouse button: {:?}", button);
       }
       Ok(None)
     }
@@ -39,7 +44,7 @@ fn simulate_input_event(
       if let Some(rdev_key) = tr::msg_to_rdev(&key) {
         simulate(&rdev::EventType::KeyRelease(rdev_key))?;
       } else {
-        println!("Unknown key: {:?}", key);
+        tracing::warn!("Unknown key: {:?}", key);
       }
       Ok(None)
     }
@@ -47,7 +52,7 @@ fn simulate_input_event(
       if let Some(rdev_key) = tr::msg_to_rdev(&key) {
         simulate(&rdev::EventType::KeyPress(rdev_key))?;
       } else {
-        println!("Unknown key: {:?}", key);
+        tracing::warn!("Unknown key: {:?}", key);
       }
       Ok(None)
     }
@@ -61,7 +66,11 @@ fn simulate_input_event(
   }
 }
 
-pub(crate) async fn simulate_receiver(mut receiver: Receiver<events::AppEvent>) -> Result<(), anyhow::Error> {
+pub async fn simulate_receiver(
+  mut receiver: Receiver<events::AppEvent>,
+  clock: ServerClock,
+  latency: LatencyRecorder,
+) -> Result<(), anyhow::Error> {
   let mut initial_position = None;
   let mut desired_position = None;
 
@@ -70,20 +79,27 @@ pub(crate) async fn simulate_receiver(mut receiver: Receiver<events::AppEvent>)
       events::AppEvent::Quit => {
         return Ok(());
       }
-      events::AppEvent::ControlEvent(_) => todo!(),
+      // Only seen in symmetric mode, where the same client also captures input
+      events::AppEvent::ControlEvent(_) => {}
       events::AppEvent::SimulationEvent(events::SimulationEvent::LocalMouseChanged(x, y)) => {
         initial_position = Some((x, y));
       }
       events::AppEvent::SimulationEvent(events::SimulationEvent::SimulateEvent(msg::SimulationEvent {
-        input_event: Some(msg::UserInputEvent { r#type: Some(event) }),
+        input_event: Some(mut input),
+        relayed_at_us,
+        ..
       })) => {
+        let Some(event) = input.r#type.take() else {
+          continue;
+        };
         if let Some(current_position) = desired_position {
           // Fail on first error?
           if let Some(next_position) = simulate_input_event(current_position, event)? {
             desired_position = Some(next_position);
           }
+          latency.record(&input, relayed_at_us, clock.now_us());
         } else {
-          println!("No mouse event yet, we do not know the current location of the mouse.");
+          tracing::debug!("No mouse event yet, we do not know the current location of the mouse.");
         }
       }
       events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::Targetted) => {
@@ -96,3 +112,38 @@ pub(crate) async fn simulate_receiver(mut receiver: Receiver<events::AppEvent>)
     }
   }
 }
+
+pub async fn listen_to_client(
+  options: Arc<Options>,
+  mut client: GrpcClient,
+  sender: Sender<events::AppEvent>,
+) -> Result<(), anyhow::Error> {
+  let request = msg::SimulateRequest {
+    workspace: options.workspace.clone(),
+    device: options.device.clone(),
+  };
+  let mut stream = client.simulate_workspace(request).await?.into_inner();
+  loop {
+    let event = match tokio::time::timeout(options.heartbeat_timeout, stream.message()).await {
+      Ok(message) => match message? {
+        Some(event) => event,
+        None => break,
+      },
+      Err(_) => {
+        tracing::error!(
+          "No heartbeat from the server in {:?}, the simulation stream is dead",
+          options.heartbeat_timeout
+        );
+        anyhow::bail!("Simulation stream timed out");
+      }
+    };
+    if event.input_event.is_none() {
+      // Only a heartbeat
+      continue;
+    }
+    sender.send(events::AppEvent::SimulationEvent(
+      events::SimulationEvent::SimulateEvent(event),
+    ))?;
+  }
+  Ok(())
+}
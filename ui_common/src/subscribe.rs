@@ -4,6 +4,7 @@ use cli_clipboard::ClipboardProvider;
 use sinnergasm::grpc_client::GrpcClient;
 use sinnergasm::options::Options;
 use sinnergasm::protos as msg;
+use sinnergasm::telemetry;
 use std::sync::Arc;
 use tokio::sync::broadcast::Sender;
 
@@ -44,32 +45,84 @@ async fn subscribe_to_workspace(
     sender.send(events::AppEvent::target(options.device.clone()))?;
   }
 
-  while let Some(msg::WorkspaceEvent {
-    event_type: Some(event_type),
-  }) = subscription.message().await?
-  {
-    println!("Subscription message: {:?}", event_type);
+  loop {
+    // The server sends heartbeats, so a stream that stays quiet for longer than the timeout is dead
+    let message = match tokio::time::timeout(options.heartbeat_timeout, subscription.message()).await {
+      Ok(message) => message?,
+      Err(_) => {
+        tracing::error!(
+          "No heartbeat from the server in {:?}, the workspace subscription is dead",
+          options.heartbeat_timeout
+        );
+        anyhow::bail!("Workspace subscription timed out");
+      }
+    };
+    let (event_type, trace_context) = match message {
+      Some(msg::WorkspaceEvent {
+        event_type: Some(event_type),
+        trace_context,
+      }) => (event_type, trace_context),
+      _ => break,
+    };
+    tracing::debug!("Subscription message: {:?}", event_type);
     match event_type {
       msg::workspace_event::EventType::Targetted(msg::Targetted { clipboard }) => {
+        // Part of the trace of the target switch that caused it
+        let span = tracing::info_span!("targetted");
+        telemetry::follow(&span, &trace_context);
+        let _entered = span.enter();
         // This should just be another clipboard listener...
-        println!("Targetted, clipboard = {:?}", &clipboard);
+        tracing::debug!("Targetted, clipboard = {:?}", &clipboard);
         if let Some(clipboard) = clipboard {
           ctx.set_contents(clipboard).expect("Unable to set clipboard");
         }
         sender.send(events::AppEvent::targetted())?;
       }
       msg::workspace_event::EventType::Untargetted(msg::Untargetted { device: _ }) => {
-        println!("Untargetted");
+        let span = tracing::info_span!("untargetted");
+        telemetry::follow(&span, &trace_context);
+        let _entered = span.enter();
+        tracing::info!("Untargetted");
         sender.send(events::AppEvent::untargetted())?;
       }
       msg::workspace_event::EventType::DownloadRequest(upload_request) => {
-        println!("Received request to upload, sending app event {:?}", upload_request);
+        tracing::info!("Received request to upload, sending app event {:?}", upload_request);
+        sender.send(events::AppEvent::SubscriptionEvent(
+          events::SubscriptionEvent::BeginUpload(upload_request, trace_context),
+        ))?;
+      }
+      msg::workspace_event::EventType::ConfigurationUpdate(msg::ConfigurationUpdate {
+        workspace: Some(workspace),
+      }) => {
         sender.send(events::AppEvent::SubscriptionEvent(
-          events::SubscriptionEvent::BeginUpload(upload_request),
+          events::SubscriptionEvent::ConfigurationUpdate(workspace),
         ))?;
       }
-      msg::workspace_event::EventType::DeviceConnected(_)
-      | msg::workspace_event::EventType::DeviceDisconnected(_)
+      msg::workspace_event::EventType::DeviceConnected(msg::DeviceConnected { device, role }) => {
+        sender.send(events::AppEvent::SubscriptionEvent(
+          events::SubscriptionEvent::DeviceConnected(
+            device,
+            msg::DeviceRole::from_i32(role).unwrap_or(msg::DeviceRole::Unknown),
+          ),
+        ))?;
+      }
+      msg::workspace_event::EventType::DeviceDisconnected(msg::DeviceDisconnected { device, role }) => {
+        sender.send(events::AppEvent::SubscriptionEvent(
+          events::SubscriptionEvent::DeviceDisconnected(
+            device,
+            msg::DeviceRole::from_i32(role).unwrap_or(msg::DeviceRole::Unknown),
+          ),
+        ))?;
+      }
+      msg::workspace_event::EventType::ServerShuttingDown(msg::ServerShuttingDown { grace_period_ms }) => {
+        // The server ends the stream itself, leaving first keeps that from looking like a failure
+        tracing::info!(
+          "The server is shutting down, transfers have {}ms to finish",
+          grace_period_ms
+        );
+        break;
+      }
+      msg::workspace_event::EventType::Heartbeat(_)
       | msg::workspace_event::EventType::TargetUpdate(_)
       | msg::workspace_event::EventType::ConfigurationUpdate(_) => {}
     }
@@ -8,6 +8,7 @@ use cli_clipboard::ClipboardContext;
 use cli_clipboard::ClipboardProvider;
 use std::sync::Arc;
 use tokio::sync::broadcast::Receiver;
+use tracing::Instrument;
 
 pub async fn launch_send_targets_task(
   receiver: Receiver<events::AppEvent>,
@@ -21,6 +22,38 @@ pub async fn launch_send_targets_task(
   return target_task;
 }
 
+fn target_request(
+  ctx: &mut ClipboardContext,
+  options: &Options,
+  device: String,
+  take_control: bool,
+) -> msg::TargetRequest {
+  msg::TargetRequest {
+    workspace: options.workspace.clone(),
+    device,
+    clipboard: match ctx.get_contents() {
+      Ok(contents) => Some(contents),
+      Err(err) => {
+        tracing::warn!("Error getting clipboard contents: {}", err);
+        None
+      }
+    },
+    take_control,
+  }
+}
+
+fn report_target(response: &msg::TargetResponse) {
+  if response.changed {
+    tracing::info!(
+      "Now targetting {}, previously {:?}",
+      response.current,
+      response.previous
+    );
+  } else {
+    tracing::info!("{} was already the target", response.current);
+  }
+}
+
 async fn send_target_requests(
   mut receiver: Receiver<events::AppEvent>,
   mut client: GrpcClient,
@@ -31,31 +64,32 @@ async fn send_target_requests(
   loop {
     match receiver.recv().await? {
       events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::RequestTarget(device)) => {
-        let request = msg::TargetRequest {
-          workspace: options.workspace.clone(),
-          device: device.clone(),
-          clipboard: match ctx.get_contents() {
-            Ok(contents) => Some(contents),
-            Err(err) => {
-              eprintln!("Error getting clipboard contents: {}", err);
-              None
-            }
-          },
-        };
-        if let Err(err) = client.target_device(request).await {
-          eprintln!("Error sending target request: {}", err);
+        // The trace of a target switch starts here and follows the events to the other devices
+        let span = tracing::info_span!("target", device = %device);
+        let request = target_request(&mut ctx, &options, device, false);
+        match client.target_device(request).instrument(span).await {
+          Ok(response) => report_target(&response.into_inner()),
+          Err(err) => tracing::error!("Error sending target request: {}", err),
+        }
+      }
+      events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::TakeControl) => {
+        let span = tracing::info_span!("take_control");
+        let request = target_request(&mut ctx, &options, options.device.clone(), true);
+        match client.target_device(request).instrument(span).await {
+          Ok(response) => report_target(&response.into_inner()),
+          Err(err) => tracing::error!("Error taking control of the workspace: {}", err),
         }
       }
       events::AppEvent::Quit => {
         return Ok(());
       }
       events::AppEvent::RequestDwonload(device, shared_file) => {
-        println!("handler: Sending download request for {:?}", shared_file);
+        tracing::info!("handler: Sending download request for {:?}", shared_file);
         let _task = spawn_download_task(client.clone(), device, shared_file, options.clone()).await;
         match _task.await {
-          Ok(Ok(())) => println!("Task completed successfully"),
-          Ok(Err(_)) => println!("Task returned an error"),
-          Err(_) => println!("Task panicked"),
+          Ok(Ok(())) => tracing::debug!("Task completed successfully"),
+          Ok(Err(_)) => tracing::warn!("Task returned an error"),
+          Err(_) => tracing::error!("Task panicked"),
         }
       }
       events::AppEvent::ControlEvent(_)
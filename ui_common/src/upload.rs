@@ -6,12 +6,14 @@ use sha2::Sha256;
 use sinnergasm::grpc_client::GrpcClient;
 use sinnergasm::options::Options;
 use sinnergasm::protos as msg;
+use sinnergasm::telemetry;
 use std::fs;
 use std::io;
 use std::io::prelude::*;
 use tokio::sync::broadcast::Receiver;
 use tokio::sync::mpsc as tokio_mpsc;
 use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::Instrument;
 
 
 // const DEFAULT_BUFFER_SIZE: usize = 4096;
@@ -24,9 +26,9 @@ pub(crate) fn compute_hash(file_path: &std::path::Path) -> Result<String, anyhow
 
   let bytes_written = io::copy(&mut file, &mut hasher)?;
   if bytes_written == 0 {
-    eprintln!("hashed 0 bytes");
+    tracing::warn!("hashed 0 bytes");
   }
-  println!("Bytes written to hasher {}.", bytes_written);
+  tracing::debug!("Bytes written to hasher {}.", bytes_written);
   let hash_bytes = hasher.finalize();
   Ok(format!("{:x}", hash_bytes))
 }
@@ -41,32 +43,27 @@ async fn upload_file(
   request: msg::UploadRequested,
   options: Arc<Options>,
 ) -> Result<(), anyhow::Error> {
-  println!("Uploading file {:?}", request);
+  tracing::info!("Uploading file");
   let file_path = std::path::Path::new(&options.shared_folder).join(&request.relative_path);
 
-  println!("Computing hash of file");
   let checksum = compute_hash(&file_path)?;
-  println!("Hash of file is {}", checksum);
+  tracing::debug!("Hash of file is {}", checksum);
   let mut file = std::fs::File::open(&file_path)?;
-  println!("Opened file {:?}", file_path);
   let metadata = file.metadata()?;
-  println!("File metadata: {:?}", metadata);
   let permissions = metadata.permissions();
-  println!("Ignoring file permissions: {:?}", permissions);
+  tracing::debug!("Ignoring file permissions: {:?}", permissions);
 
   let buffer_size = request.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
   let number_of_chunks = div_ceil(metadata.len(), buffer_size);
-  println!(
+  tracing::info!(
     "File size: {}, buffer size: {}, number of chunks: {}",
     metadata.len(),
     buffer_size,
     number_of_chunks
   );
-  println!("Creating channel");
   let (sender, receiver) = tokio_mpsc::unbounded_channel();
-  println!("Creating receiver stream");
   let receiver_stream = UnboundedReceiverStream::new(receiver);
-  println!("Sending upload request");
+  tracing::debug!("Sending upload request");
   sender.send(msg::UploadRequest {
     r#type: Some(msg::upload_request::Type::Initiate(msg::InitiateUpload {
       workspace: options.workspace.clone(),
@@ -80,15 +77,15 @@ async fn upload_file(
     })),
   })?;
 
-  println!("Awaiting upload response");
+  tracing::debug!("Awaiting upload response");
   let mut stream = client.upload_file(receiver_stream).await?.into_inner();
 
-  println!("Sending chunks");
+  tracing::debug!("Sending chunks");
   while let Some(msg::UploadResponse {
     r#type: Some(event_type),
   }) = stream.message().await?
   {
-    println!("Received upload response: {:?}", event_type);
+    tracing::trace!("Received upload response: {:?}", event_type);
     match event_type {
       msg::upload_response::Type::Request(msg::ChunkRequest { offset }) => {
         let byte_offset = offset * buffer_size;
@@ -96,7 +93,7 @@ async fn upload_file(
         let mut buf = vec![0; buffer_size as usize];
         if let Ok(size) = file.read(&mut buf) {
           if size == 0 {
-            eprintln!("Read 0 bytes!");
+            tracing::warn!("Read 0 bytes!");
           }
           sender.send(msg::UploadRequest {
             r#type: Some(msg::upload_request::Type::Chunk(msg::SharedFileChunk {
@@ -105,7 +102,7 @@ async fn upload_file(
             })),
           })?;
         } else {
-          eprintln!("Unable to read file");
+          tracing::error!("Unable to read file");
         }
       }
       msg::upload_response::Type::Complete(msg::UploadComplete {}) => {
@@ -125,19 +122,27 @@ pub async fn listen_for_uploads(
   loop {
     match receiver.recv().await? {
       events::AppEvent::Quit => {
-        println!("Received quit event");
+        tracing::info!("Received quit event");
         return Ok(());
       }
-      events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::BeginUpload(request)) => {
-        println!("Received request to upload {:?}", request);
+      events::AppEvent::SubscriptionEvent(events::SubscriptionEvent::BeginUpload(request, trace_context)) => {
+        let span = tracing::info_span!(
+          "upload",
+          downloader = %request.download_device,
+          path = %request.relative_path
+        );
+        telemetry::follow(&span, &trace_context);
+        tracing::info!(parent: &span, "Received request to upload");
         let client_clone = client.clone();
         let options_clone = options.clone();
-        let task = tokio::task::spawn(async move {
-          println!("within spawn: Uploading file");
-          if let Err(err) = upload_file(client_clone, request, options_clone).await {
-            eprintln!("Error uploading file: {}", err);
+        let task = tokio::task::spawn(
+          async move {
+            if let Err(err) = upload_file(client_clone, request, options_clone).await {
+              tracing::error!("Error uploading file: {}", err);
+            }
           }
-        });
+          .instrument(span),
+        );
         task.await?;
       }
       _ => {}